@@ -0,0 +1,29 @@
+//! Compares serial and parallel batch (de)serialization of a large captured session.
+//!
+//! Run with `cargo bench --bench batch --features rayon` to include the parallel variants.
+
+use std::time::Instant;
+
+use ledswarm_protocol::frame::{batch, Frame};
+
+const FRAMES: u16 = 50_000;
+
+fn measure<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    println!("{name:<24} {:>10.2?}", start.elapsed());
+    result
+}
+
+fn main() {
+    let frames: Vec<Frame> = (0 .. FRAMES).map(|tick| Frame::tick(tick).sender_id(tick % 16)).collect();
+
+    let buffers = measure("serialize_all", || batch::serialize_all(&frames).unwrap());
+    measure("deserialize_all", || batch::deserialize_all(&buffers).unwrap());
+
+    #[cfg(feature = "rayon")]
+    {
+        measure("par_serialize_all", || batch::par_serialize_all(&frames).unwrap());
+        measure("par_deserialize_all", || batch::par_deserialize_all(&buffers).unwrap());
+    }
+}
@@ -0,0 +1,66 @@
+//! Counts the allocations made while encoding a frame, comparing the preallocated `Frame::to_bytes` to assembling the
+//! same bytes from separately allocated parts, as it was done before.
+//!
+//! Run with `cargo bench --bench serialize`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use ledswarm_protocol::frame::{Frame, ENCODING_BINCODE, MAGIC};
+use ledswarm_protocol::wire;
+
+const FRAMES: u16 = 50_000;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn measure(name: &str, frames: &[Frame], encode: impl Fn(&Frame) -> Vec<u8>) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    let bytes: usize = frames.iter().map(|frame| encode(frame).len()).sum();
+
+    let elapsed = start.elapsed();
+    let per_frame = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / frames.len() as f64;
+    println!("{name:<16} {elapsed:>10.2?} {per_frame:>6.2} allocations/frame ({bytes} bytes)");
+}
+
+/// Encode the body and the tagged body into their own buffers before copying them into the final one.
+fn assembled(frame: &Frame) -> Vec<u8> {
+    let body = bincode::serialize(frame).unwrap();
+
+    let mut tagged = Vec::new();
+    tagged.push(ENCODING_BINCODE);
+    tagged.extend_from_slice(&(body.len() as u16).to_le_bytes());
+    tagged.extend_from_slice(&body);
+
+    wire::frame_bytes(MAGIC, &tagged, frame.header.ranging_bytes)
+}
+
+fn main() {
+    let frames: Vec<Frame> = (0 .. FRAMES).map(|tick| Frame::tick(tick).sender_id(tick % 16)).collect();
+
+    measure("assembled", &frames, assembled);
+    measure("to_bytes", &frames, |frame| frame.to_bytes().unwrap());
+}
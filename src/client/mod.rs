@@ -0,0 +1,128 @@
+//! A JSON-serialiable message format for WebSocket communication between master nodes and connected GUI clients.
+//! 
+//! This message frame is simpler than the one used for UWB, as the client messages are only transferred between master nodes and connected GUI clients. Using a client link not only provides
+//! a way to control and monitor controller games, it's also useful to run diagnostics and do time-travel debugging, inspecting controller events and game states in real-time.
+//!
+//! The messages themselves are the [`ClientMessage`]s also carried by UWB frames, so there is a single definition of
+//! everything a GUI client can send or receive:
+//!
+//! ```rust
+//! use ledswarm_protocol::client::ClientFrame;
+//! use ledswarm_protocol::frame::ClientMessage;
+//!
+//! let frame = ClientFrame::new(ClientMessage::SetBrightness(0.5));
+//! assert_eq!(frame.payload, ClientMessage::SetBrightness(0.5));
+//! ```
+
+pub mod ping;
+#[cfg(feature = "schema")]
+pub mod schema;
+
+use nanoid::nanoid;
+#[cfg(feature = "serde")]
+use serde_derive::{Serialize, Deserialize};
+
+pub use crate::frame::ClientMessage;
+#[cfg(feature = "json")]
+use crate::frame::FrameError;
+
+/// Unknown fields are rejected instead of ignored, so typos in JSON sent by the GUI surface as errors.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct ClientFrame {
+    pub header:  ClientHeader,
+    pub payload: ClientMessage,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct ClientHeader {
+    /// A ten-byte long unique message identifier generated by the `nanoid` crate.
+    pub id: String,
+}
+
+/// The former name of the client messages, which used to be a separate definition.
+#[deprecated(note = "use `ClientMessage` instead")]
+pub type ClientPayload = ClientMessage;
+
+impl ClientHeader {
+    pub fn new() -> Self {
+        Self { id: nanoid!(10) }
+    }
+}
+
+impl Default for ClientHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientFrame {
+    /// Wrap a message into a frame with a fresh message ID.
+    pub fn new(payload: ClientMessage) -> Self {
+        Self {
+            header: ClientHeader::new(),
+            payload,
+        }
+    }
+
+    /// Encode the frame as JSON for the WebSocket link.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, FrameError> {
+        serde_json::to_string(self).map_err(|_e| FrameError::SerializeError)
+    }
+
+    /// Decode a frame received as JSON over the WebSocket link.
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Self, FrameError> {
+        serde_json::from_str(json).map_err(|_e| FrameError::SerializeError)
+    }
+}
+
+impl From<ClientMessage> for ClientFrame {
+    fn from(payload: ClientMessage) -> Self {
+        Self::new(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_frame_from_client_message() {
+        let frame = ClientFrame::from(ClientMessage::EndRound);
+
+        assert_eq!(frame.payload, ClientMessage::EndRound);
+        assert_eq!(frame.header.id.len(), 10);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn client_frame_json_round_trip() {
+        let frame = ClientFrame::new(ClientMessage::SetBrightness(0.5));
+
+        let json = frame.to_json().unwrap();
+        assert_eq!(json, format!(r#"{{"header":{{"id":"{}"}},"payload":{{"SetBrightness":0.5}}}}"#, frame.header.id));
+        assert_eq!(frame, ClientFrame::from_json(&json).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn unknown_json_fields_are_rejected() {
+        let valid = r#"{"header":{"id":"V1StGXR8_Z"},"payload":{"FadeBrightness":{"target":0.5,"durationMs":10}}}"#;
+        assert!(ClientFrame::from_json(valid).is_ok());
+
+        for typo in [
+            r#"{"header":{"id":"V1StGXR8_Z","idd":"x"},"payload":"EndRound"}"#,
+            r#"{"header":{"id":"V1StGXR8_Z"},"payload":"EndRound","extra":1}"#,
+            r#"{"header":{"id":"V1StGXR8_Z"},"payload":{"FadeBrightness":{"target":0.5,"durationMs":10,"duration_ms":10}}}"#,
+        ] {
+            assert_eq!(ClientFrame::from_json(typo), Err(FrameError::SerializeError));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! An application-level keepalive for the WebSocket link, which also measures its round-trip latency.
+//!
+//! Browsers don't expose the ping frames of the WebSocket protocol, so a GUI can't tell a dead connection from a quiet
+//! one with them. Instead, either side sends `ClientMessage::Ping`s and the other answers each with a
+//! `ClientMessage::Pong`, see [`ClientMessage::pong_for`]. A [`Pinger`] hands out the nonces and matches the answers.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::frame::ClientMessage;
+
+/// How many unanswered pings a [`Pinger`] remembers. Older ones are forgotten, their pongs no longer match.
+pub const MAX_OUTSTANDING_PINGS: usize = 8;
+
+/// Creates pings with unique nonces and measures the latency of the pongs answering them.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Pinger {
+    next_nonce:  u32,
+    outstanding: VecDeque<(u32, Instant)>,
+}
+
+impl Pinger {
+    /// A pinger starting from a nonce derived from the clock, so a late pong from a previous connection is unlikely
+    /// to match a ping of this one.
+    pub fn new() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since_epoch| since_epoch.subsec_nanos());
+        Self::with_first_nonce(nanos)
+    }
+
+    pub fn with_first_nonce(nonce: u32) -> Self {
+        Self { next_nonce: nonce, outstanding: VecDeque::with_capacity(MAX_OUTSTANDING_PINGS) }
+    }
+
+    /// A ping with a fresh nonce, sent at `now`.
+    pub fn ping(&mut self, now: Instant) -> ClientMessage {
+        let nonce = self.next_nonce;
+        self.next_nonce = self.next_nonce.wrapping_add(1);
+
+        if self.outstanding.len() == MAX_OUTSTANDING_PINGS {
+            self.outstanding.pop_front();
+        }
+        self.outstanding.push_back((nonce, now));
+
+        ClientMessage::Ping { nonce }
+    }
+
+    /// The round-trip latency if the message is a `Pong` answering an outstanding ping, which is no longer
+    /// outstanding afterwards.
+    pub fn on_message(&mut self, msg: &ClientMessage, now: Instant) -> Option<Duration> {
+        let ClientMessage::Pong { nonce } = msg else {
+            return None;
+        };
+
+        let position = self.outstanding.iter().position(|(sent_nonce, _)| sent_nonce == nonce)?;
+        let (_, sent_at) = self.outstanding.remove(position)?;
+        Some(now.saturating_duration_since(sent_at))
+    }
+
+    /// The number of pings awaiting their pong.
+    pub fn outstanding(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    /// When the oldest unanswered ping was sent, for declaring the connection dead once it is too long ago.
+    pub fn oldest_outstanding(&self) -> Option<Instant> {
+        self.outstanding.front().map(|(_, sent_at)| *sent_at)
+    }
+}
+
+impl Default for Pinger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pongs_measure_latency() {
+        let start = Instant::now();
+        let mut pinger = Pinger::with_first_nonce(u32::MAX);
+
+        let first = pinger.ping(start);
+        let second = pinger.ping(start + Duration::from_millis(5));
+        assert_eq!(first, ClientMessage::Ping { nonce: u32::MAX });
+        assert_eq!(second, ClientMessage::Ping { nonce: 0 });
+
+        let pong = second.pong_for().unwrap();
+        assert_eq!(pinger.on_message(&pong, start + Duration::from_millis(12)), Some(Duration::from_millis(7)));
+        assert_eq!(pinger.on_message(&pong, start + Duration::from_millis(13)), None);
+        assert_eq!(pinger.on_message(&ClientMessage::Pong { nonce: 1 }, start), None);
+        assert_eq!(pinger.on_message(&first, start), None);
+        assert_eq!(pinger.oldest_outstanding(), Some(start));
+        assert_eq!(ClientMessage::EndRound.pong_for(), None);
+    }
+
+    #[test]
+    fn old_pings_are_forgotten() {
+        let now = Instant::now();
+        let mut pinger = Pinger::with_first_nonce(0);
+        for _ in 0 ..= MAX_OUTSTANDING_PINGS {
+            pinger.ping(now);
+        }
+
+        assert_eq!(pinger.outstanding(), MAX_OUTSTANDING_PINGS);
+        assert_eq!(pinger.on_message(&ClientMessage::Pong { nonce: 0 }, now), None);
+        assert_eq!(pinger.on_message(&ClientMessage::Pong { nonce: 1 }, now), Some(Duration::ZERO));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        use crate::client::ClientFrame;
+
+        let frame = ClientFrame::new(ClientMessage::Ping { nonce: 7 });
+        let json = frame.to_json().unwrap();
+        assert_eq!(json, format!(r#"{{"header":{{"id":"{}"}},"payload":{{"Ping":{{"nonce":7}}}}}}"#, frame.header.id));
+        assert_eq!(ClientFrame::from_json(&json), Ok(frame));
+
+        let pong = ClientFrame::from_json(r#"{"header":{"id":"V1StGXR8_Z"},"payload":{"Pong":{"nonce":7}}}"#).unwrap();
+        assert_eq!(pong.payload, ClientMessage::Pong { nonce: 7 });
+    }
+}
@@ -0,0 +1,32 @@
+//! A JSON Schema of the messages exchanged with GUI clients, available with the `schema` feature.
+//!
+//! The schema follows the serde attributes of the client types, so it describes exactly the JSON accepted by
+//! [`ClientFrame::from_json`](crate::client::ClientFrame::from_json), and can be used to generate types for the GUI
+//! or to validate messages before sending them.
+
+use crate::client::ClientFrame;
+
+/// The JSON Schema of a [`ClientFrame`], with [`ClientMessage`](crate::frame::ClientMessage) and every other type it
+/// refers to listed in its `definitions`.
+pub fn json_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(ClientFrame)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_lists_client_messages() {
+        let schema = json_schema();
+        assert_eq!(schema["title"], "ClientFrame");
+        assert_eq!(schema["additionalProperties"], false);
+
+        let message = schema["definitions"]["ClientMessage"].to_string();
+        for variant in ["SetBrightness", "FadeBrightness", "StartRound", "EndRound", "RequestSnapshot", "Snapshot", "LobbyState", "Log"] {
+            assert!(message.contains(&format!("\"{}\"", variant)), "{} missing", variant);
+        }
+        assert!(message.contains("\"durationMs\""));
+        assert!(schema["definitions"]["NodeInfo"].is_object());
+    }
+}
@@ -0,0 +1,66 @@
+//! Encoding and decoding of many frames at once, for dumping and replaying large captured sessions.
+//!
+//! With the `rayon` feature, the `par_*` variants spread the work across all cores. They produce exactly the same
+//! output in the same order as their serial counterparts.
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::frame::{Frame, FrameError};
+
+/// Encode every frame for the wire, failing on the first frame which can not be serialized.
+pub fn serialize_all(frames: &[Frame]) -> Result<Vec<Vec<u8>>, FrameError> {
+    frames.iter().map(Frame::to_bytes).collect()
+}
+
+/// Decode every buffer into a frame, failing on the first buffer which is not a valid frame.
+pub fn deserialize_all(buffers: &[Vec<u8>]) -> Result<Vec<Frame>, FrameError> {
+    buffers.iter().map(|buffer| Frame::try_from(buffer.as_slice())).collect()
+}
+
+/// Parallel version of [`serialize_all`].
+#[cfg(feature = "rayon")]
+pub fn par_serialize_all(frames: &[Frame]) -> Result<Vec<Vec<u8>>, FrameError> {
+    frames.par_iter().map(Frame::to_bytes).collect()
+}
+
+/// Parallel version of [`deserialize_all`].
+#[cfg(feature = "rayon")]
+pub fn par_deserialize_all(buffers: &[Vec<u8>]) -> Result<Vec<Frame>, FrameError> {
+    buffers.par_iter().map(|buffer| Frame::try_from(buffer.as_slice())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Vec<Frame> {
+        (0 .. 100).map(|tick| Frame::tick(tick).sender_id(tick % 7)).collect()
+    }
+
+    #[test]
+    fn serialize_deserialize_all() {
+        let frames = session();
+
+        let buffers = serialize_all(&frames).unwrap();
+        assert_eq!(frames, deserialize_all(&buffers).unwrap());
+    }
+
+    #[test]
+    fn deserialize_all_fails_on_invalid_buffer() {
+        let mut buffers = serialize_all(&session()).unwrap();
+        buffers[3][0] = b'X';
+
+        assert!(deserialize_all(&buffers).is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_serial() {
+        let frames = session();
+
+        let buffers = serialize_all(&frames).unwrap();
+        assert_eq!(buffers, par_serialize_all(&frames).unwrap());
+        assert_eq!(deserialize_all(&buffers).unwrap(), par_deserialize_all(&buffers).unwrap());
+    }
+}
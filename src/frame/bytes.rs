@@ -0,0 +1,76 @@
+//! Compact (de)serialization of `Vec<u8>` payload fields, for use with `#[serde(with = "crate::frame::bytes")]`.
+//!
+//! Serde treats a `Vec<u8>` like any other sequence, which bincode encodes fine as a length prefix followed by the
+//! bytes, but which turns into a JSON array with one number per byte. With this module, human-readable formats get a
+//! standard base64 string instead when the `json` feature is enabled, while bincode stays byte-for-byte identical to
+//! a plain `Vec<u8>`.
+
+use serde::de::Deserializer;
+use serde::ser::Serializer;
+use serde::Deserialize;
+
+pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    #[cfg(feature = "json")]
+    if serializer.is_human_readable() {
+        use base64::Engine;
+
+        return serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes));
+    }
+
+    serializer.serialize_bytes(bytes)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    #[cfg(feature = "json")]
+    if deserializer.is_human_readable() {
+        use base64::Engine;
+        use serde::de::Error;
+
+        let encoded = String::deserialize(deserializer)?;
+        return base64::engine::general_purpose::STANDARD.decode(encoded).map_err(Error::custom);
+    }
+
+    Vec::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::Options;
+    use serde_derive::{Serialize, Deserialize};
+
+    use crate::wire;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Fragment {
+        index: u8,
+        #[serde(with = "crate::frame::bytes")]
+        data:  Vec<u8>,
+    }
+
+    #[derive(Serialize)]
+    struct PlainFragment {
+        index: u8,
+        data:  Vec<u8>,
+    }
+
+    #[test]
+    fn bincode_matches_plain_vec() {
+        let fragment = Fragment { index: 3, data: vec![0, 1, 2, 255] };
+        let plain = PlainFragment { index: 3, data: vec![0, 1, 2, 255] };
+        let encoded = wire::bincode_options().serialize(&fragment).unwrap();
+
+        assert_eq!(encoded, wire::bincode_options().serialize(&plain).unwrap());
+        assert_eq!(wire::bincode_options().deserialize::<Fragment>(&encoded).unwrap(), fragment);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_uses_base64() {
+        let fragment = Fragment { index: 3, data: b"LEDswarm".to_vec() };
+        let json = serde_json::to_string(&fragment).unwrap();
+
+        assert_eq!(json, r#"{"index":3,"data":"TEVEc3dhcm0="}"#);
+        assert_eq!(serde_json::from_str::<Fragment>(&json).unwrap(), fragment);
+        assert!(serde_json::from_str::<Fragment>(r#"{"index":3,"data":"not base64!"}"#).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! The CRC-16 protecting the header of frames in the standard encoding, so relays can trust the routing fields
+//! without decoding or checking the payload.
+//!
+//! The checksum follows the encoded header and precedes the payload in the body. It is the CRC-16/CCITT-FALSE
+//! (polynomial `0x1021`, initial value `0xFFFF`) of the encoded header, stored little-endian. The payload is not
+//! covered, a relay forwards a frame with a corrupt payload and leaves rejecting it to the destination.
+//!
+//! Compact frames carry no header checksum, since their header can't be decoded without the payload.
+
+#[cfg(feature = "serde")]
+use std::io;
+
+#[cfg(feature = "serde")]
+use bincode::Options;
+
+#[cfg(feature = "serde")]
+use crate::frame::{framed_body, Frame, FrameError, ENCODING_BINCODE};
+#[cfg(feature = "serde")]
+use crate::wire;
+
+/// The number of bytes of the header checksum in the body of a frame.
+pub const HEADER_CHECKSUM_LEN: usize = 2;
+
+/// The CRC-16/CCITT-FALSE of the bytes.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    update(0xFFFF, bytes)
+}
+
+fn update(mut crc: u16, bytes: &[u8]) -> u16 {
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0 .. 8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Forwards everything written to the inner writer, computing the CRC-16 of it on the way.
+#[cfg(feature = "serde")]
+pub(crate) struct ChecksumWriter<W> {
+    inner: W,
+    crc:   u16,
+}
+
+#[cfg(feature = "serde")]
+impl<W: io::Write> ChecksumWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, crc: 0xFFFF }
+    }
+
+    pub(crate) fn finish(self) -> (W, u16) {
+        (self.inner, self.crc)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<W: io::Write> io::Write for ChecksumWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(bytes)?;
+        self.crc = update(self.crc, &bytes[.. written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Frame {
+    /// The checksum of the encoded header, as stored in the standard encoding of this frame.
+    pub fn header_checksum(&self) -> Result<u16, FrameError> {
+        let header = wire::bincode_options().serialize(&self.header).map_err(|_e| FrameError::SerializeError)?;
+        Ok(crc16(&header))
+    }
+
+    /// Whether the buffer holds a frame in the standard encoding whose header matches its checksum, regardless of
+    /// the payload. Compact frames have no header checksum and are never verified.
+    pub fn verify_header_checksum(bytes: &[u8]) -> bool {
+        framed_body(bytes).is_ok_and(|framed| framed.encoding == ENCODING_BINCODE && super::decode_header(framed.body).is_ok())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use bincode::Options;
+
+    use super::*;
+    use crate::frame::PREFIX_LEN;
+    use crate::wire::RANGING_LEN;
+
+    #[test]
+    fn crc16_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc16(b""), 0xFFFF);
+    }
+
+    #[test]
+    fn corrupt_payload_keeps_a_valid_header() {
+        let frame = Frame::join_response(7, 3).universe(2);
+        let mut bytes = frame.to_bytes().unwrap();
+        let header_len = wire::bincode_options().serialized_size(&frame.header).unwrap() as usize;
+
+        let checksum_at = PREFIX_LEN + header_len;
+        assert_eq!(bytes[checksum_at .. checksum_at + HEADER_CHECKSUM_LEN], frame.header_checksum().unwrap().to_le_bytes());
+
+        // The assigned ID at the very end of the payload.
+        let last_payload_byte = bytes.len() - RANGING_LEN - 1;
+        bytes[last_payload_byte] ^= 0x40;
+        assert!(Frame::verify_header_checksum(&bytes));
+        assert_eq!(Frame::peek_header(&bytes), Ok(frame.header.clone()));
+        assert_ne!(Frame::try_from(&bytes[..]).map(|frame| frame.payload), Ok(frame.payload));
+    }
+
+    #[test]
+    fn corrupt_header_is_detected() {
+        let frame = Frame::join_response(7, 3).universe(2);
+        let mut bytes = frame.to_bytes().unwrap();
+        // The low byte of the sender ID, past the timestamp, lifetime and message ID.
+        let sender_at = PREFIX_LEN + 8 + frame.header.timestamp.len() + 1 + 8 + 10;
+        bytes[sender_at] ^= 0x01;
+
+        assert!(!Frame::verify_header_checksum(&bytes));
+        assert_eq!(Frame::peek_header(&bytes), Err(FrameError::BadHeaderChecksum));
+        assert_eq!(Frame::try_from(&bytes[..]), Err(FrameError::BadHeaderChecksum));
+
+        assert!(!Frame::verify_header_checksum(&frame.serialize_compact().unwrap()));
+    }
+}
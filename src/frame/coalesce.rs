@@ -0,0 +1,100 @@
+//! Coalescing of several small frames for the same target into a single `Batch` frame, to save the fixed airtime
+//! overhead every transmission pays.
+//!
+//! A receiving node splits a batch back into its parts with [`Frame::split_batch`] before acting on them, so the rest
+//! of the stack never sees `Batch` payloads.
+
+use crate::frame::{Frame, FramePayload};
+
+impl Frame {
+    /// Combine the frames into a single frame carrying all of their payloads in a [`FramePayload::Batch`], in order.
+    ///
+    /// Returns `None` unless all of the following hold:
+    ///
+    /// - There is at least one frame, and all frames share the same sender, target and universe.
+    /// - Every payload [`is_batchable`](FramePayload::is_batchable).
+    /// - No frame requires an acknowledgement, since those are tracked by their own message ID.
+    /// - The batch still fits into a single frame on the wire.
+    ///
+    /// The batch gets a fresh message ID, the tick of the last frame and the longest lifetime of all frames.
+    #[cfg(feature = "serde")]
+    pub fn coalesce(frames: &[Frame]) -> Option<Frame> {
+        let (first, last) = (frames.first()?, frames.last()?);
+        let compatible = frames.iter().all(|frame| {
+            frame.header.sender_id == first.header.sender_id
+                && frame.header.target_id == first.header.target_id
+                && frame.header.universe == first.header.universe
+                && !frame.header.requires_acknowledgement
+                && frame.payload.is_batchable()
+        });
+        if !compatible {
+            return None;
+        }
+
+        let payloads = frames.iter().map(|frame| frame.payload.clone()).collect();
+        let lifetime = frames.iter().map(|frame| frame.header.lifetime).max()?;
+        let batch = Frame::new()
+            .payload(FramePayload::Batch(payloads))
+            .sender_id(first.header.sender_id)
+            .map_header(|header| header.target_id = first.header.target_id)
+            .universe(first.header.universe)
+            .current_tick(last.header.current_tick)
+            .lifetime(lifetime);
+
+        batch.encoded_len().is_ok().then_some(batch)
+    }
+
+    /// Split a `Batch` frame into one frame per payload, each carrying a copy of the batch header. Other frames are
+    /// returned as they are.
+    ///
+    /// The parts share the message ID of the batch, so duplicates have to be filtered before splitting.
+    pub fn split_batch(self) -> Vec<Frame> {
+        match self.payload {
+            FramePayload::Batch(payloads) => payloads
+                .into_iter()
+                .map(|payload| Frame { header: self.header.clone(), payload, lifetime_overridden: self.lifetime_overridden })
+                .collect(),
+            _ => vec![self],
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::frame::{ControllerMessage, FrameKind};
+
+    fn score(team: u8, score: i32, tick: u16) -> Frame {
+        Frame::new()
+            .message(ControllerMessage::TeamScore { team, score })
+            .sender_id(0)
+            .universe(1)
+            .current_tick(tick)
+    }
+
+    #[test]
+    fn coalesce_and_split_scores() {
+        let scores = [score(0, 3, 10), score(1, 5, 11), score(2, -1, 12)];
+
+        let batch = Frame::coalesce(&scores).unwrap();
+        assert_eq!(batch.payload.kind(), FrameKind::Batch);
+        assert_eq!(batch.header.current_tick, 12);
+        assert!(batch.encoded_len().unwrap() < scores.iter().map(|frame| frame.encoded_len().unwrap()).sum());
+
+        let decoded = Frame::try_from(&batch.to_bytes().unwrap()[..]).unwrap();
+        let parts = decoded.split_batch();
+        let payloads: Vec<_> = parts.iter().map(|part| part.payload.clone()).collect();
+        assert_eq!(payloads, scores.map(|frame| frame.payload));
+        assert!(parts.iter().all(|part| part.header.sender_id == 0 && part.header.universe == 1));
+    }
+
+    #[test]
+    fn incompatible_frames_are_not_coalesced() {
+        assert_eq!(Frame::coalesce(&[]), None);
+        assert_eq!(Frame::coalesce(&[score(0, 1, 0), score(1, 1, 0).universe(2)]), None);
+        assert_eq!(Frame::coalesce(&[score(0, 1, 0), score(1, 1, 0).require_confirmation()]), None);
+        assert_eq!(Frame::coalesce(&[score(0, 1, 0), Frame::new().sender_id(0).universe(1)]), None);
+        let tick = Frame::tick(3);
+        assert_eq!(tick.clone().split_batch(), [tick]);
+    }
+}
@@ -0,0 +1,299 @@
+//! A compact encoding of frames which leaves out header fields at their default values, for the smallest possible
+//! ticks and acknowledgements on a saturated link.
+//!
+//! Compact frames share the framing of the standard encoding, but carry the format tag [`ENCODING_COMPACT`]. Their
+//! body starts with a bitmask of the optional header fields which are present, followed by the always present fields
+//! and the present optional ones in this order, each encoded like in the standard encoding:
+//!
+//! ```text
+//! +------+-----------+------------+-----------+--------------+-----------+
+//! | mask | timestamp | message_id | sender_id | current_tick | optional… | payload
+//! | 1    | string    | string     | u16       | u16          |           |
+//! +------+-----------+------------+-----------+--------------+-----------+
+//! ```
+//!
+//! | Bit | Field                      | Present if                                          | Encoded as |
+//! |-----|----------------------------|-----------------------------------------------------|------------|
+//! | 0   | `lifetime`                 | it differs from the default lifetime of the payload | `u8`       |
+//! | 1   | `target_id`                | it is `Some`                                        | `u16`      |
+//! | 2   | `requires_acknowledgement` | it is `true`                                        | nothing    |
+//! | 3   | `acked_message_id`         | it is `Some`                                        | string     |
+//! | 4   | `universe`                 | it is not the `GLOBAL_UNIVERSE`                     | `u8`       |
+//! | 5   | `signature`                | it is `Some`                                        | signature  |
+//! | 6   | `visited`                  | it is not empty                                     | `Vec<u16>` |
+//! | 7   | the extended mask          | any bit of the extended mask is set                 | `u8`       |
+//!
+//! The extended mask directly follows the mask, for header fields beyond the first seven. Its optional fields follow
+//! the ones of the mask:
+//!
+//! | Bit | Field                      | Present if                                          | Encoded as |
+//! |-----|----------------------------|-----------------------------------------------------|------------|
+//! | 0   | `ack_timeout_ticks`        | it is `Some`                                        | `u16`      |
+//! | 1   | `deadline_tick`            | it is `Some`                                        | `u16`      |
+//!
+//! The other bits of the extended mask are reserved and must be zero. The ranging bytes trail the body as in every
+//! other frame.
+
+use bincode::Options;
+
+use crate::frame::{
+    default_lifetime,
+    Frame,
+    FrameError,
+    FrameHeader,
+    FramePayload,
+    FrameSignature,
+    GLOBAL_UNIVERSE,
+    ENCODING_COMPACT,
+    MAGIC,
+    MAX_FRAME_SIZE,
+};
+use crate::wire;
+
+const LIFETIME: u8 = 1 << 0;
+const TARGET_ID: u8 = 1 << 1;
+const REQUIRES_ACKNOWLEDGEMENT: u8 = 1 << 2;
+const ACKED_MESSAGE_ID: u8 = 1 << 3;
+const UNIVERSE: u8 = 1 << 4;
+const SIGNATURE: u8 = 1 << 5;
+const VISITED: u8 = 1 << 6;
+const EXTENDED: u8 = 1 << 7;
+
+const ACK_TIMEOUT_TICKS: u8 = 1 << 0;
+const DEADLINE_TICK: u8 = 1 << 1;
+const EXTENDED_RESERVED: u8 = !(ACK_TIMEOUT_TICKS | DEADLINE_TICK);
+
+impl Frame {
+    /// Encode the frame for the wire in the compact encoding described in [`crate::frame::compact`], leaving out header fields at
+    /// their default values.
+    ///
+    /// Compact frames are decoded by [`Frame::deserialize_compact`], and by all other decoding functions like
+    /// `Frame::try_from`, which pick the encoding by the format tag. Fails with `FrameError::SerializeError` if the body
+    /// is longer than [`MAX_FRAME_SIZE`].
+    pub fn serialize_compact(&self) -> Result<Vec<u8>, FrameError> {
+        self.debug_assert_invariants();
+
+        let body = compact_body(self).map_err(|_e| FrameError::SerializeError)?;
+        if body.len() > MAX_FRAME_SIZE {
+            return Err(FrameError::SerializeError);
+        }
+        let body_len = body.len() as u16;
+
+        let mut tagged = Vec::with_capacity(3 + body.len());
+        tagged.push(ENCODING_COMPACT);
+        tagged.extend_from_slice(&body_len.to_le_bytes());
+        tagged.extend_from_slice(&body);
+
+        Ok(wire::frame_bytes(MAGIC, &tagged, self.header.ranging_bytes))
+    }
+
+    /// Decode a frame previously encoded with [`Frame::serialize_compact`], rejecting frames in any other encoding
+    /// with `FrameError::UnsupportedEncoding`.
+    pub fn deserialize_compact(bytes: &[u8]) -> Result<Frame, FrameError> {
+        match bytes.get(MAGIC.len()) {
+            Some(&tag) if tag != ENCODING_COMPACT && bytes.starts_with(MAGIC) => Err(FrameError::UnsupportedEncoding(tag)),
+            _ => Frame::from_bytes(bytes, false),
+        }
+    }
+}
+
+fn compact_body(frame: &Frame) -> bincode::Result<Vec<u8>> {
+    let header = &frame.header;
+    let options = wire::bincode_options();
+
+    let mut mask = 0;
+    if header.lifetime != default_lifetime(frame.payload.kind()) {
+        mask |= LIFETIME;
+    }
+    if header.target_id.is_some() {
+        mask |= TARGET_ID;
+    }
+    if header.requires_acknowledgement {
+        mask |= REQUIRES_ACKNOWLEDGEMENT;
+    }
+    if header.acked_message_id.is_some() {
+        mask |= ACKED_MESSAGE_ID;
+    }
+    if header.universe != GLOBAL_UNIVERSE {
+        mask |= UNIVERSE;
+    }
+    if header.signature.is_some() {
+        mask |= SIGNATURE;
+    }
+    if !header.visited.is_empty() {
+        mask |= VISITED;
+    }
+
+    let mut extended = 0;
+    if header.ack_timeout_ticks.is_some() {
+        extended |= ACK_TIMEOUT_TICKS;
+    }
+    if header.deadline_tick.is_some() {
+        extended |= DEADLINE_TICK;
+    }
+
+    let mut body = vec![mask];
+    if extended != 0 {
+        body[0] |= EXTENDED;
+        body.push(extended);
+    }
+    options.serialize_into(&mut body, &header.timestamp)?;
+    options.serialize_into(&mut body, &header.message_id)?;
+    options.serialize_into(&mut body, &header.sender_id)?;
+    options.serialize_into(&mut body, &header.current_tick)?;
+    if mask & LIFETIME != 0 {
+        options.serialize_into(&mut body, &header.lifetime)?;
+    }
+    if let Some(target_id) = &header.target_id {
+        options.serialize_into(&mut body, target_id)?;
+    }
+    if let Some(acked_message_id) = &header.acked_message_id {
+        options.serialize_into(&mut body, acked_message_id)?;
+    }
+    if mask & UNIVERSE != 0 {
+        options.serialize_into(&mut body, &header.universe)?;
+    }
+    if let Some(signature) = &header.signature {
+        options.serialize_into(&mut body, signature)?;
+    }
+    if mask & VISITED != 0 {
+        options.serialize_into(&mut body, &header.visited)?;
+    }
+    if let Some(ack_timeout_ticks) = &header.ack_timeout_ticks {
+        options.serialize_into(&mut body, ack_timeout_ticks)?;
+    }
+    if let Some(deadline_tick) = &header.deadline_tick {
+        options.serialize_into(&mut body, deadline_tick)?;
+    }
+    options.serialize_into(&mut body, &frame.payload)?;
+
+    Ok(body)
+}
+
+/// Decode the body of a compact frame. The ranging bytes are left at zero, they are restored from the framing.
+pub(crate) fn decode_body(mut body: &[u8]) -> Result<Frame, FrameError> {
+    let (&mask, rest) = body.split_first().ok_or(FrameError::SerializeError)?;
+    body = rest;
+
+    let mut extended = 0;
+    if mask & EXTENDED != 0 {
+        let (&byte, rest) = body.split_first().ok_or(FrameError::SerializeError)?;
+        // An extended mask without any bit set is never encoded.
+        if byte == 0 || byte & EXTENDED_RESERVED != 0 {
+            return Err(FrameError::SerializeError);
+        }
+        extended = byte;
+        body = rest;
+    }
+
+    let options = wire::bincode_options();
+
+    let timestamp: String = options.deserialize_from(&mut body).map_err(|_e| FrameError::SerializeError)?;
+    let message_id: String = options.deserialize_from(&mut body).map_err(|_e| FrameError::SerializeError)?;
+    let sender_id: u16 = options.deserialize_from(&mut body).map_err(|_e| FrameError::SerializeError)?;
+    let current_tick: u16 = options.deserialize_from(&mut body).map_err(|_e| FrameError::SerializeError)?;
+    let lifetime: Option<u8> = optional(mask & LIFETIME, &mut body)?;
+    let target_id: Option<u16> = optional(mask & TARGET_ID, &mut body)?;
+    let acked_message_id: Option<String> = optional(mask & ACKED_MESSAGE_ID, &mut body)?;
+    let universe: Option<u8> = optional(mask & UNIVERSE, &mut body)?;
+    let signature: Option<FrameSignature> = optional(mask & SIGNATURE, &mut body)?;
+    let visited: Option<Vec<u16>> = optional(mask & VISITED, &mut body)?;
+    let ack_timeout_ticks: Option<u16> = optional(extended & ACK_TIMEOUT_TICKS, &mut body)?;
+    let deadline_tick: Option<u16> = optional(extended & DEADLINE_TICK, &mut body)?;
+    let payload: FramePayload = options.deserialize_from(&mut body).map_err(|_e| FrameError::SerializeError)?;
+
+    let header = FrameHeader {
+        timestamp,
+        lifetime: lifetime.unwrap_or_else(|| default_lifetime(payload.kind())),
+        message_id,
+        sender_id,
+        target_id,
+        requires_acknowledgement: mask & REQUIRES_ACKNOWLEDGEMENT != 0,
+        acked_message_id,
+        current_tick,
+        universe: universe.unwrap_or(GLOBAL_UNIVERSE),
+        ranging_bytes: [0; 4],
+        signature,
+        visited: visited.unwrap_or_default(),
+        ack_timeout_ticks,
+        deadline_tick,
+    };
+
+    Ok(Frame { header, payload, lifetime_overridden: false })
+}
+
+fn optional<T: serde::de::DeserializeOwned>(present: u8, body: &mut &[u8]) -> Result<Option<T>, FrameError> {
+    if present == 0 {
+        return Ok(None);
+    }
+
+    wire::bincode_options().deserialize_from(body).map(Some).map_err(|_e| FrameError::SerializeError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::ProtocolMessage;
+
+    #[test]
+    fn all_defaults_round_trip() {
+        let frame = Frame::tick(7);
+
+        let bytes = frame.serialize_compact().unwrap();
+        assert_eq!(bytes[MAGIC.len()], ENCODING_COMPACT);
+        assert_eq!(bytes[MAGIC.len() + 3], 0, "no optional field is present");
+        assert!(bytes.len() < frame.encoded_len().unwrap());
+        assert_eq!(Frame::deserialize_compact(&bytes), Ok(frame.clone()));
+        assert_eq!(Frame::try_from(&bytes[..]), Ok(frame));
+    }
+
+    #[test]
+    fn fully_populated_round_trip() {
+        let mut frame = Frame::control(ProtocolMessage::Acknowledged { message_id: "V1StGXR8_Z".to_string() })
+            .lifetime(5)
+            .sender_id(3)
+            .target_id(9)
+            .require_confirmation()
+            .ack_timeout(20)
+            .deadline(u16::MAX)
+            .also_acks("abcdefghij")
+            .current_tick(u16::MAX)
+            .universe(4)
+            .ranging(0x0102_0304_u32);
+        frame.header.signature = Some(FrameSignature { signer: 3, bytes: [7; 64] });
+        frame.mark_visited(6);
+
+        let bytes = frame.serialize_compact().unwrap();
+        assert_eq!(bytes[MAGIC.len() + 3], u8::MAX);
+        assert_eq!(bytes[MAGIC.len() + 4], !EXTENDED_RESERVED);
+        assert_eq!(Frame::deserialize_compact(&bytes), Ok(frame.clone()));
+        assert_eq!(Frame::peek_header(&bytes), Ok(frame.header));
+    }
+
+    #[test]
+    fn largest_body_round_trips() {
+        let mut frame = Frame::tick(7);
+        let bytes = frame.serialize_compact().unwrap();
+        let other_body_len = bytes.len() - (MAGIC.len() + 3) - frame.header.ranging_bytes.len() - frame.header.message_id.len();
+
+        frame.header.message_id = "x".repeat(MAX_FRAME_SIZE - other_body_len);
+        let bytes = frame.serialize_compact().unwrap();
+        assert_eq!(u16::from_le_bytes([bytes[MAGIC.len() + 1], bytes[MAGIC.len() + 2]]) as usize, MAX_FRAME_SIZE);
+        assert_eq!(Frame::deserialize_compact(&bytes), Ok(frame.clone()));
+
+        frame.header.message_id.push('x');
+        assert_eq!(frame.serialize_compact(), Err(FrameError::SerializeError));
+    }
+
+    #[test]
+    fn standard_frames_are_not_compact() {
+        let bytes = Frame::tick(7).to_bytes().unwrap();
+        assert_eq!(Frame::deserialize_compact(&bytes), Err(FrameError::UnsupportedEncoding(crate::frame::ENCODING_BINCODE)));
+
+        let mut bytes = Frame::tick(7).ack_timeout(3).serialize_compact().unwrap();
+        bytes[MAGIC.len() + 4] |= 1 << 7;
+        assert_eq!(Frame::try_from(&bytes[..]), Err(FrameError::SerializeError));
+        bytes[MAGIC.len() + 4] = 0;
+        assert_eq!(Frame::try_from(&bytes[..]), Err(FrameError::SerializeError));
+    }
+}
@@ -0,0 +1,110 @@
+//! Deflate compression of large payloads, available with the `compression` feature.
+//!
+//! A compressed frame keeps its header, while its payload is replaced by a `ProtocolMessage::Compressed` carrying the
+//! compressed bincode encoding of the original payload. Small payloads barely shrink and would only cost the receiver
+//! time, so payloads are only compressed from [`COMPRESSION_THRESHOLD`] bytes on, and only if that makes them smaller.
+//! Peers announce that they can decompress payloads with `Capabilities::COMPRESSION`.
+
+use bincode::Options;
+
+use crate::frame::{Frame, FrameError, FramePayload, ProtocolMessage};
+use crate::wire;
+
+/// The `algo` of payloads compressed with raw deflate.
+pub const ALGO_DEFLATE: u8 = 1;
+
+/// Payloads whose encoding is shorter than this many bytes are left uncompressed.
+pub const COMPRESSION_THRESHOLD: usize = 128;
+
+/// The maximum length of a decompressed payload, so a malicious frame can't make the receiver allocate without bound.
+pub const MAX_DECOMPRESSED_LEN: usize = 16 * 1024;
+
+/// The compression level of deflate, from 0 to 10, trading compression speed on the sender for airtime.
+const DEFLATE_LEVEL: u8 = 6;
+
+impl Frame {
+    /// Replace a payload of at least [`COMPRESSION_THRESHOLD`] encoded bytes by its compressed form, returning the frame
+    /// unchanged if the payload is smaller, already compressed, or wouldn't shrink.
+    pub fn compress_payload(mut self) -> Result<Frame, FrameError> {
+        if matches!(self.payload, FramePayload::ProtocolMessage(ProtocolMessage::Compressed { .. })) {
+            return Ok(self);
+        }
+
+        let encoded = payload_options().serialize(&self.payload).map_err(|_e| FrameError::SerializeError)?;
+        if encoded.len() < COMPRESSION_THRESHOLD {
+            return Ok(self);
+        }
+
+        let data = miniz_oxide::deflate::compress_to_vec(&encoded, DEFLATE_LEVEL);
+        let compressed = FramePayload::ProtocolMessage(ProtocolMessage::Compressed { algo: ALGO_DEFLATE, data });
+        if payload_options().serialized_size(&compressed).map_err(|_e| FrameError::SerializeError)? < encoded.len() as u64 {
+            self.payload = compressed;
+        }
+
+        Ok(self)
+    }
+
+    /// Restore the original payload of a frame compressed by [`Frame::compress_payload`], returning other frames
+    /// unchanged.
+    pub fn decompress_payload(mut self) -> Result<Frame, FrameError> {
+        let FramePayload::ProtocolMessage(ProtocolMessage::Compressed { algo, data }) = &self.payload else {
+            return Ok(self);
+        };
+        if *algo != ALGO_DEFLATE {
+            return Err(FrameError::DecompressionFailed);
+        }
+
+        let encoded = miniz_oxide::inflate::decompress_to_vec_with_limit(data, MAX_DECOMPRESSED_LEN)
+            .map_err(|_e| FrameError::DecompressionFailed)?;
+        self.payload = payload_options().deserialize(&encoded).map_err(|_e| FrameError::DecompressionFailed)?;
+
+        Ok(self)
+    }
+}
+
+/// The options of the wire format, but allowing payloads larger than a frame before compression.
+fn payload_options() -> impl Options {
+    wire::bincode_options().with_limit(MAX_DECOMPRESSED_LEN as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::ClientMessage;
+
+    fn large_frame() -> Frame {
+        Frame::new().client_message(ClientMessage::LobbyState { ready: (1 .. 100).collect(), not_ready: vec![200; 50] })
+    }
+
+    #[test]
+    fn large_payloads_round_trip() {
+        let frame = large_frame().sender_id(3).universe(2);
+        let compressed = frame.clone().compress_payload().unwrap();
+
+        assert!(matches!(compressed.payload, FramePayload::ProtocolMessage(ProtocolMessage::Compressed { algo: ALGO_DEFLATE, .. })));
+        assert_eq!(compressed.header, frame.header);
+        assert!(compressed.encoded_len().unwrap() < frame.encoded_len().unwrap());
+
+        let decoded = Frame::try_from(&compressed.to_bytes().unwrap()[..]).unwrap();
+        assert_eq!(decoded.decompress_payload(), Ok(frame));
+    }
+
+    #[test]
+    fn small_payloads_stay_uncompressed() {
+        let tick = Frame::tick(4);
+        assert_eq!(tick.clone().compress_payload(), Ok(tick.clone()));
+        assert_eq!(tick.clone().decompress_payload(), Ok(tick));
+
+        let compressed = large_frame().compress_payload().unwrap();
+        assert_eq!(compressed.clone().compress_payload(), Ok(compressed));
+    }
+
+    #[test]
+    fn bogus_data_fails() {
+        let unknown = Frame::control(ProtocolMessage::Compressed { algo: 200, data: vec![1, 2, 3] });
+        assert_eq!(unknown.decompress_payload(), Err(FrameError::DecompressionFailed));
+
+        let garbage = Frame::control(ProtocolMessage::Compressed { algo: ALGO_DEFLATE, data: vec![0xff; 8] });
+        assert_eq!(garbage.decompress_payload(), Err(FrameError::DecompressionFailed));
+    }
+}
@@ -0,0 +1,121 @@
+//! Bounded `Debug` output of frames, for logging frames whose payload carries large byte buffers or strings.
+
+use crate::frame::Frame;
+
+impl Frame {
+    /// The `Debug` representation of the frame, with every string and every list of numbers longer than `max` cut to
+    /// its first `max` bytes or numbers, followed by `…(N more)`. Large compressed or encrypted payloads then take up
+    /// a bounded part of a log line, while all other fields are shown in full.
+    pub fn truncated_debug(&self, max: usize) -> String {
+        truncate_debug(&format!("{:?}", self), max)
+    }
+}
+
+fn truncate_debug(debug: &str, max: usize) -> String {
+    let mut out = String::with_capacity(debug.len().min(256));
+    let mut rest = debug;
+
+    while let Some(at) = rest.find(['"', '[']) {
+        out.push_str(&rest[.. at]);
+        rest = &rest[at ..];
+
+        let consumed = if rest.starts_with('"') { truncate_string(rest, max, &mut out) } else { truncate_numbers(rest, max, &mut out) };
+        rest = &rest[consumed ..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Copies the string literal at the start of `debug` to `out`, returning its length. Escape sequences are never cut.
+fn truncate_string(debug: &str, max: usize, out: &mut String) -> usize {
+    let mut chars = debug.char_indices().skip(1);
+    let mut kept = 1;
+
+    while let Some((at, c)) = chars.next() {
+        let end = match c {
+            '"' => {
+                if at - 1 <= max {
+                    out.push_str(&debug[..= at]);
+                } else {
+                    out.push_str(&debug[.. kept]);
+                    out.push_str(&format!("…({} more)\"", at - kept));
+                }
+                return at + 1;
+            },
+            // `\u{…}` escapes end at their closing brace, all others after the next character.
+            '\\' => match chars.next() {
+                Some((_, 'u')) => chars.find(|&(_, c)| c == '}').map_or(debug.len(), |(at, _)| at + 1),
+                Some((at, c)) => at + c.len_utf8(),
+                None => debug.len(),
+            },
+            _ => at + c.len_utf8(),
+        };
+
+        if end - 1 <= max {
+            kept = end;
+        }
+    }
+
+    // Unterminated, which `Debug` never produces.
+    out.push_str(debug);
+    debug.len()
+}
+
+/// Copies the list at the start of `debug` to `out` if it only holds numbers, returning its length, or just the opening
+/// bracket otherwise, so nested lists are looked at on their own.
+fn truncate_numbers(debug: &str, max: usize, out: &mut String) -> usize {
+    let list = debug.find(']').map(|end| &debug[1 .. end]);
+    let numbers = list.filter(|list| !list.is_empty() && list.split(", ").all(|n| n.parse::<f64>().is_ok()));
+
+    let Some(numbers) = numbers else {
+        out.push('[');
+        return 1;
+    };
+
+    let count = numbers.split(", ").count();
+    if count <= max {
+        out.push_str(&debug[..= numbers.len() + 1]);
+    } else {
+        out.push('[');
+        for (i, number) in numbers.split(", ").take(max).enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            out.push_str(number);
+        }
+        out.push_str(&format!("{}…({} more)]", if max > 0 { ", " } else { "" }, count - max));
+    }
+
+    numbers.len() + 2
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frame::{Frame, ProtocolMessage};
+
+    #[test]
+    fn large_payloads_are_cut() {
+        let frame = Frame::control(ProtocolMessage::Compressed { algo: 1, data: vec![0xAB; 800] });
+        let full = format!("{:?}", frame);
+        let truncated = frame.truncated_debug(12);
+
+        assert!(full.len() > 3000);
+        assert!(truncated.len() < 800, "{}", truncated);
+        assert!(truncated.contains("data: [171, 171, 171, 171, 171, 171, 171, 171, 171, 171, 171, 171, …(788 more)]"));
+        // Short fields are kept as they are, only the timestamp is longer than 12 bytes.
+        assert!(truncated.contains("ranging_bytes: [0, 0, 0, 0]"));
+        assert!(truncated.contains(&format!("timestamp: \"{}…({} more)\"", &frame.header.timestamp[.. 12], frame.header.timestamp.len() - 12)));
+        assert!(truncated.contains(&format!("{:?}", frame.header.message_id)));
+        assert_eq!(frame.truncated_debug(usize::MAX), full);
+    }
+
+    #[test]
+    fn long_strings_are_cut_between_escapes() {
+        let mut frame = Frame::tick(0);
+        frame.header.message_id = "ab\"cdefgh".to_string();
+
+        assert!(frame.truncated_debug(4).contains(r#"message_id: "ab\"…(6 more)""#));
+        assert!(frame.truncated_debug(3).contains(r#"message_id: "ab…(8 more)""#));
+    }
+}
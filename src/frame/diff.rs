@@ -0,0 +1,87 @@
+//! Field-by-field comparison of two frames, for debugging frames which changed unexpectedly on their way through the mesh.
+
+use core::fmt::Debug;
+
+use crate::frame::{Frame, FrameHeader};
+
+/// A single field whose value differs between two frames, both sides formatted with `Debug`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FieldDiff {
+    /// The path of the field within the frame, like `header.current_tick` or `payload`.
+    pub field: &'static str,
+    pub left:  String,
+    pub right: String,
+}
+
+impl Frame {
+    /// List every field which differs between this frame and `other`, in declaration order.
+    ///
+    /// Header fields are compared one by one, the payload is compared as a whole.
+    pub fn diff(&self, other: &Frame) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        // Destructured exhaustively, so a new header field can not be forgotten here.
+        let FrameHeader {
+            timestamp,
+            lifetime,
+            message_id,
+            sender_id,
+            target_id,
+            requires_acknowledgement,
+            acked_message_id,
+            current_tick,
+            universe,
+            ranging_bytes,
+            signature,
+            visited,
+            ack_timeout_ticks,
+            deadline_tick,
+        } = &self.header;
+        let right = &other.header;
+
+        compare(&mut diffs, "header.timestamp", timestamp, &right.timestamp);
+        compare(&mut diffs, "header.lifetime", lifetime, &right.lifetime);
+        compare(&mut diffs, "header.message_id", message_id, &right.message_id);
+        compare(&mut diffs, "header.sender_id", sender_id, &right.sender_id);
+        compare(&mut diffs, "header.target_id", target_id, &right.target_id);
+        compare(&mut diffs, "header.requires_acknowledgement", requires_acknowledgement, &right.requires_acknowledgement);
+        compare(&mut diffs, "header.acked_message_id", acked_message_id, &right.acked_message_id);
+        compare(&mut diffs, "header.current_tick", current_tick, &right.current_tick);
+        compare(&mut diffs, "header.universe", universe, &right.universe);
+        compare(&mut diffs, "header.ranging_bytes", ranging_bytes, &right.ranging_bytes);
+        compare(&mut diffs, "header.signature", signature, &right.signature);
+        compare(&mut diffs, "header.visited", visited, &right.visited);
+        compare(&mut diffs, "header.ack_timeout_ticks", ack_timeout_ticks, &right.ack_timeout_ticks);
+        compare(&mut diffs, "header.deadline_tick", deadline_tick, &right.deadline_tick);
+        compare(&mut diffs, "payload", &self.payload, &other.payload);
+
+        diffs
+    }
+}
+
+fn compare<T: PartialEq + Debug>(diffs: &mut Vec<FieldDiff>, field: &'static str, left: &T, right: &T) {
+    if left != right {
+        diffs.push(FieldDiff {
+            field,
+            left:  format!("{:?}", left),
+            right: format!("{:?}", right),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_only_tick() {
+        let sent = Frame::join_request(10);
+        let received = sent.clone().current_tick(11);
+
+        assert_eq!(sent.diff(&received), vec![FieldDiff {
+            field: "header.current_tick",
+            left:  "10".to_string(),
+            right: "11".to_string(),
+        }]);
+        assert!(sent.diff(&sent).is_empty());
+    }
+}
@@ -1,11 +1,55 @@
 //! Error types for when something goes wrong with an UWB frame, like serialization or deserialization errors.
 
+#[cfg(feature = "serde")]
 use serde_derive::{Serialize, Deserialize};
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+use crate::mesh::NodeRole;
+use crate::wire::WireError;
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FrameError {
     /// An error occurred while serializing or deserializing the packet.
     SerializeError,
+    /// The buffer to decode was empty, so nothing arrived at all, as opposed to a buffer holding something other than a frame.
+    EmptyBuffer,
     /// The magic string "LEDswarm" was not found at the start of the byte buffer, so the received datagram is not a valid LEDswarm UWB packet.
     NoMagicString(String),
-}
\ No newline at end of file
+    /// The buffer ended before the frame did, `expected` bytes were needed to decode it but only `actual` bytes were available.
+    TooShort { expected: usize, actual: usize },
+    /// A frame was decoded in strict mode, but `count` more bytes followed it in the buffer.
+    TrailingBytes { count: usize },
+    /// The format tag following the magic string names an encoding this version of the protocol cannot decode.
+    UnsupportedEncoding(u8),
+    /// The frame carries no signature, or its signature was not made by the given key over the contents of the frame.
+    BadSignature,
+    /// The frame was decoded successfully, but a node with this role should not act on it.
+    NotForRole(NodeRole),
+    /// A frame was built with a hop TTL of zero, so it would be dropped before reaching anyone.
+    ZeroTtl,
+    /// A legacy `UwbMessage` has no counterpart in the requested frame payload type.
+    NoFrameEquivalent,
+    /// The name or identifier of a game mode does not refer to a valid `GameMode`.
+    UnknownGameMode,
+    /// The encoded frame takes `needed` bytes, which don't fit into a fixed-capacity buffer of `capacity` bytes.
+    BufferTooSmall { needed: usize, capacity: usize },
+    /// A `ProtocolMessage::Compressed` payload uses an unknown algorithm, or its data doesn't decompress to a payload.
+    DecompressionFailed,
+    /// The header of a frame doesn't match its checksum, so it was corrupted in transit.
+    BadHeaderChecksum,
+    /// The frame breaks one of the invariants checked by `Frame::validate`, described by the message.
+    InvalidFrame(String),
+    /// The timestamp of a frame, given in the message, is not an RFC 3339 date and time.
+    BadTimestamp(String),
+    /// An airtime was estimated for a data rate of zero bits per second, at which nothing is ever transmitted.
+    ZeroBitrate,
+}
+
+impl From<WireError> for FrameError {
+    fn from(error: WireError) -> Self {
+        match error {
+            WireError::TooShort { expected, actual } => Self::TooShort { expected, actual },
+            WireError::NoMagicString(found) => Self::NoMagicString(found),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Composable predicates over frames, for subscriptions, metrics and capture tooling which only care about some frames.
+
+use crate::frame::{Frame, FrameKind};
+
+/// A set of conditions a frame has to meet, all of which must hold for the frame to match.
+///
+/// An empty filter matches every frame.
+///
+/// ```rust
+/// use ledswarm_protocol::frame::{Frame, FrameFilter, FrameKind};
+///
+/// let filter = FrameFilter::new().by_kind(FrameKind::Protocol).broadcast_only();
+/// assert!(Frame::tick(5).matches_filter(&filter));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct FrameFilter {
+    sender_id:      Option<u16>,
+    kind:           Option<FrameKind>,
+    universe:       Option<u8>,
+    broadcast_only: bool,
+}
+
+impl FrameFilter {
+    /// A filter matching every frame.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match frames sent by the given node.
+    pub fn by_sender(mut self, sender_id: u16) -> Self {
+        self.sender_id = Some(sender_id);
+        self
+    }
+
+    /// Only match frames with the given kind of payload.
+    pub fn by_kind(mut self, kind: FrameKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only match frames of the given universe.
+    pub fn by_universe(mut self, universe: u8) -> Self {
+        self.universe = Some(universe);
+        self
+    }
+
+    /// Only match frames without a target ID, which are meant for every node.
+    pub fn broadcast_only(mut self) -> Self {
+        self.broadcast_only = true;
+        self
+    }
+
+    /// Whether the frame meets every condition of this filter.
+    pub fn matches(&self, frame: &Frame) -> bool {
+        self.sender_id.is_none_or(|sender_id| frame.header.sender_id == sender_id)
+            && self.kind.is_none_or(|kind| frame.payload.kind() == kind)
+            && self.universe.is_none_or(|universe| frame.header.universe == universe)
+            && (!self.broadcast_only || frame.header.target_id.is_none())
+    }
+}
+
+impl Frame {
+    /// Whether this frame meets every condition of the filter, see [`FrameFilter`].
+    pub fn matches_filter(&self, filter: &FrameFilter) -> bool {
+        filter.matches(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{ControllerMessage, MASTER_ID};
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        assert!(Frame::new().matches_filter(&FrameFilter::new()));
+        assert!(Frame::join_request(3).matches_filter(&FrameFilter::new()));
+    }
+
+    #[test]
+    fn combined_predicates() {
+        let frame = Frame::new()
+            .message(ControllerMessage::Discover)
+            .sender_id(4)
+            .universe(2);
+        let filter = FrameFilter::new()
+            .by_sender(4)
+            .by_kind(FrameKind::Controller)
+            .by_universe(2)
+            .broadcast_only();
+
+        assert!(frame.matches_filter(&filter));
+        assert!(!frame.clone().universe(3).matches_filter(&filter));
+        assert!(!frame.clone().sender_id(5).matches_filter(&filter));
+        assert!(!frame.clone().target_id(MASTER_ID).matches_filter(&filter));
+        assert!(!Frame::tick(0).sender_id(4).universe(2).matches_filter(&filter));
+    }
+}
@@ -0,0 +1,72 @@
+//! Encoding frames into fixed-capacity `heapless::Vec`s, available with the `heapless` feature.
+//!
+//! The encoded frame is written straight into the stack-allocated buffer, without an intermediate `Vec`. Decoding needs
+//! no counterpart, since `Frame::try_from` works on any `&[u8]`, including the contents of a `heapless::Vec`.
+
+use std::io;
+
+use crate::frame::{Frame, FrameError, ENCODING_BINCODE, MAGIC, PREFIX_LEN};
+use crate::wire::RANGING_LEN;
+
+/// Appends to a `heapless::Vec`, failing once its capacity is exhausted.
+struct FixedWriter<'a, const N: usize>(&'a mut heapless::Vec<u8, N>);
+
+impl<const N: usize> io::Write for FixedWriter<'_, N> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(bytes).map_err(|()| io::ErrorKind::WriteZero)?;
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Frame {
+    /// Encode the frame for the wire into a buffer of capacity `N`, or return `FrameError::BufferTooSmall` if the
+    /// encoded frame is longer than that.
+    pub fn to_heapless<const N: usize>(&self) -> Result<heapless::Vec<u8, N>, FrameError> {
+        self.debug_assert_invariants();
+
+        let body_len = self.body_len()?;
+        let needed = PREFIX_LEN + body_len as usize + RANGING_LEN;
+        if needed > N {
+            return Err(FrameError::BufferTooSmall { needed, capacity: N });
+        }
+
+        let mut buffer = heapless::Vec::new();
+        let mut writer = FixedWriter(&mut buffer);
+        io::Write::write_all(&mut writer, MAGIC).map_err(|_e| FrameError::SerializeError)?;
+        io::Write::write_all(&mut writer, &[ENCODING_BINCODE]).map_err(|_e| FrameError::SerializeError)?;
+        io::Write::write_all(&mut writer, &body_len.to_le_bytes()).map_err(|_e| FrameError::SerializeError)?;
+        self.write_body(&mut writer)?;
+        io::Write::write_all(&mut writer, &self.header.ranging_bytes).map_err(|_e| FrameError::SerializeError)?;
+
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_frame_fits() {
+        let mut frame = Frame::tick(5);
+        frame.header.timestamp = "now".to_string();
+
+        let bytes = frame.to_heapless::<128>().unwrap();
+        assert_eq!(&bytes[..], &frame.to_bytes().unwrap()[..]);
+        assert_eq!(Frame::try_from(&bytes[..]), Ok(frame));
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let mut frame = Frame::tick(5);
+        frame.header.timestamp = "now".to_string();
+        let needed = frame.encoded_len().unwrap();
+
+        assert_eq!(frame.to_heapless::<32>(), Err(FrameError::BufferTooSmall { needed, capacity: 32 }));
+        assert_eq!(frame.to_heapless::<80>().map(|bytes| bytes.len()), Ok(needed));
+    }
+}
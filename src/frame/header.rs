@@ -1,10 +1,15 @@
 //! A data structure for the header of a frame, which contains metadata about the frame, such as the sender and target IDs, the universe number, and the current tick.
 
+#[cfg(feature = "serde")]
 use serde_derive::{Serialize, Deserialize};
 use nanoid::nanoid;
-use chrono::DateTime;
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+use chrono::{DateTime, FixedOffset};
+
+use crate::frame::{default_lifetime, FrameError, FrameKind};
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FrameHeader {
     /// When the frame was sent, as a string representation of a `DateTime` object.
     pub timestamp: String,
@@ -18,12 +23,83 @@ pub struct FrameHeader {
     pub target_id: Option<u16>,
     // If set, the sender of the frame expects an acknowledgement from the receiver and will retry until it receives one.
     pub requires_acknowledgement: bool,
+    /// The ID of a previously received message which is acknowledged by this frame, piggybacking the acknowledgement onto a frame which is sent anyway.
+    pub acked_message_id: Option<String>,
     /// The current repeating synchronization tick as an integer between 0 and 65535.
     pub current_tick: u16,
     /// Used to separate different logical networks in the same physical network, to play multiple games next to each other without interference.
     pub universe: u8,
     /// The ranging data from the UWB module, which is used to calculate the distance between the sender and the receiver.
     pub ranging_bytes: [u8; 4],
+    /// An Ed25519 signature proving that the frame was sent by `signer`, see `Frame::sign` with the `crypto` feature.
+    pub signature: Option<FrameSignature>,
+    /// The IDs of the nodes which relayed the frame so far, oldest first and at most [`MAX_VISITED`] of them, so a
+    /// flooded frame isn't relayed again by the same node after travelling around a loop.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub visited: Vec<u16>,
+    /// How many ticks the sender waits for an acknowledgement before sending the frame again, instead of the default
+    /// of its `AckManager`. Only meaningful together with `requires_acknowledgement`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ack_timeout_ticks: Option<u16>,
+    /// The last tick at which the frame is still worth delivering, like for effects which have to be shown in sync.
+    /// Relays drop the frame after it, regardless of its lifetime.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deadline_tick: Option<u16>,
+}
+
+/// How many relaying nodes a frame records in `FrameHeader::visited`. When more nodes relay it, the oldest entries are
+/// dropped, the lifetime limits how far the frame travels anyway.
+pub const MAX_VISITED: usize = 8;
+
+/// An Ed25519 signature over the [canonical bytes](crate::frame::Frame::canonical_bytes) of a frame.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FrameSignature {
+    /// The ID of the node whose key made the signature, usually the sender of the frame.
+    pub signer: u16,
+    /// The 64 signature bytes.
+    #[cfg_attr(feature = "serde", serde(with = "signature_bytes"))]
+    pub bytes: [u8; 64],
+}
+
+/// Serde only implements its traits for arrays of up to 32 elements, so the signature is (de)serialized as a tuple,
+/// which bincode encodes without a length prefix.
+#[cfg(feature = "serde")]
+mod signature_bytes {
+    use core::fmt;
+
+    use serde::de::{Deserializer, Error, SeqAccess, Visitor};
+    use serde::ser::{SerializeTuple, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 64], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(bytes.len())?;
+        for byte in bytes {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 64], D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = [u8; 64];
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("64 signature bytes")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[u8; 64], A::Error> {
+                let mut bytes = [0; 64];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = seq.next_element()?.ok_or_else(|| Error::invalid_length(i, &self))?;
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_tuple(64, BytesVisitor)
+    }
 }
 
 /*
@@ -39,26 +115,102 @@ self.uwb_out_tx.send(UwbPacket {
 impl FrameHeader {
     pub fn new() -> Self {
         Self {
-            timestamp: DateTime::<chrono::Local>::from(chrono::Local::now()).to_rfc3339(),
-            lifetime: 2,
+            timestamp: chrono::Local::now().to_rfc3339(),
+            lifetime: default_lifetime(FrameKind::Empty),
             message_id: nanoid!(10),
             sender_id: u16::MAX,
             requires_acknowledgement: false,
+            acked_message_id: None,
             target_id: None,
             current_tick: 0,
             universe: 0,
             ranging_bytes: [0; 4],
+            signature: None,
+            visited: Vec::new(),
+            ack_timeout_ticks: None,
+            deadline_tick: None,
         }
     }
+
+    /// The number of hops the frame may still travel before it is dropped, which is stored as the `lifetime`.
+    pub fn ttl_remaining(&self) -> u8 {
+        self.lifetime
+    }
+
+    /// The time the frame was sent by its original sender, parsed from the `timestamp` string.
+    pub fn origin_timestamp(&self) -> Result<DateTime<FixedOffset>, FrameError> {
+        DateTime::parse_from_rfc3339(&self.timestamp).map_err(|_e| FrameError::BadTimestamp(self.timestamp.clone()))
+    }
+
+    /// The sender ID together with the universe, which identifies the sender across all universes, see [`scoped_id`].
+    pub fn scoped_sender(&self) -> u32 {
+        scoped_id(self.universe, self.sender_id)
+    }
+
+    /// The target ID together with the universe, `None` for broadcasts, see [`scoped_id`].
+    pub fn scoped_target(&self) -> Option<u32> {
+        self.target_id.map(|target_id| scoped_id(self.universe, target_id))
+    }
+}
+
+/// Pack a node ID and its universe into a single value, which unlike the node ID alone doesn't collide between the
+/// universes, for keying logs and statistics. The universe goes into bits 16 to 23, the node ID into the low 16 bits.
+pub fn scoped_id(universe: u8, id: u16) -> u32 {
+    (universe as u32) << 16 | id as u32
+}
+
+/// Split a value packed by [`scoped_id`] into the universe and the node ID.
+pub fn unpack_scoped(scoped: u32) -> (u8, u16) {
+    ((scoped >> 16) as u8, scoped as u16)
+}
+
+impl Default for FrameHeader {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl Into<Vec<u8>> for FrameHeader {
-    fn into(self) -> Vec<u8> {
-        let mut bytes = Vec::new();
+impl From<FrameHeader> for Vec<u8> {
+    fn from(_header: FrameHeader) -> Vec<u8> {
+        let bytes = Vec::new();
 
         //bytes.push(self.timestamp.len() as u8);
         //bytes.extend_from_slice(&self.timestamp.as_bytes());
 
         bytes
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_ids_round_trip() {
+        let mut header = FrameHeader::new();
+        header.universe = 3;
+        header.sender_id = 42;
+
+        assert_eq!(header.scoped_sender(), 0x03_002A);
+        assert_eq!(header.scoped_target(), None);
+        header.target_id = Some(u16::MAX);
+        assert_eq!(header.scoped_target(), Some(0x03_FFFF));
+
+        for (universe, id) in [(0, 0), (3, 42), (255, u16::MAX)] {
+            assert_eq!(unpack_scoped(scoped_id(universe, id)), (universe, id));
+        }
+        assert_ne!(scoped_id(1, 42), scoped_id(2, 42));
+    }
+
+    #[test]
+    fn origin_timestamp_parses_once() {
+        let mut header = FrameHeader::new();
+        header.timestamp = "2024-05-01T12:30:00.250+02:00".to_string();
+        let timestamp = header.origin_timestamp().unwrap();
+        assert_eq!(timestamp.timestamp_millis(), 1_714_559_400_250);
+        assert_eq!(timestamp.offset().local_minus_utc(), 2 * 3600);
+        assert!(FrameHeader::new().origin_timestamp().is_ok());
+
+        header.timestamp = "now".to_string();
+        assert_eq!(header.origin_timestamp(), Err(FrameError::BadTimestamp("now".to_string())));
+    }
+}
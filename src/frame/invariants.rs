@@ -0,0 +1,117 @@
+//! Assertions on the invariants every frame should uphold, checked in debug builds whenever a frame is encoded and on
+//! demand for received frames.
+
+use crate::frame::{ClientMessage, ControllerMessage, Frame, FrameError, FrameHeader, FramePayload, ProtocolMessage, MASTER_ID};
+use crate::mesh::ack::MAX_ACK_BATCH;
+
+impl Frame {
+    /// Panic in debug builds if the frame breaks an invariant, doing nothing in release builds.
+    ///
+    /// Every frame is checked when it is encoded, so bugs building bogus frames surface during development instead of
+    /// as confusing behaviour on the receiving node. The checked invariants are:
+    ///
+    /// - The message ID is not empty.
+    /// - Brightness values of `SetBrightness`, `FadeBrightness` and `BrightnessReport` lie between 0.0 and 1.0.
+    /// - `JoinResponse`s assign neither the master ID nor the unassigned ID 65535.
+    /// - `Batch`es are not nested.
+    /// - `AcknowledgedBatch`es carry at most [`MAX_ACK_BATCH`] message IDs.
+    pub fn debug_assert_invariants(&self) {
+        if cfg!(debug_assertions) {
+            if let Some(violation) = self.invariant_violation() {
+                panic!("frame {} breaks an invariant: {}", self.header.message_id, violation);
+            }
+        }
+    }
+
+    /// Check the invariants listed at [`Frame::debug_assert_invariants`], in any build. Use this on received frames,
+    /// which were encoded by other, possibly buggy nodes.
+    pub fn validate(&self) -> Result<(), FrameError> {
+        match self.invariant_violation() {
+            Some(violation) => Err(FrameError::InvalidFrame(violation.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate the frame and split it into its header and payload, for processing a frame right after receiving it.
+    pub fn try_into_parts_checked(self) -> Result<(FrameHeader, FramePayload), FrameError> {
+        self.validate()?;
+        Ok((self.header, self.payload))
+    }
+
+    fn invariant_violation(&self) -> Option<&'static str> {
+        if self.header.message_id.is_empty() {
+            return Some("the message ID is empty");
+        }
+
+        payload_violation(&self.payload, false)
+    }
+}
+
+fn payload_violation(payload: &FramePayload, in_batch: bool) -> Option<&'static str> {
+    let brightness = match payload {
+        FramePayload::ControllerMessage(ControllerMessage::FadeBrightness { target, .. }) => Some(*target),
+        FramePayload::ClientMessage(ClientMessage::FadeBrightness { target, .. }) => Some(*target),
+        FramePayload::ControllerMessage(ControllerMessage::BrightnessReport { brightness }) => Some(*brightness),
+        FramePayload::ClientMessage(ClientMessage::SetBrightness(brightness)) => Some(*brightness),
+        FramePayload::ClientMessage(ClientMessage::BrightnessReport { brightness, .. }) => Some(*brightness),
+        _ => None,
+    };
+    if brightness.is_some_and(|brightness| !(0.0 ..= 1.0).contains(&brightness)) {
+        return Some("the brightness is not between 0.0 and 1.0");
+    }
+
+    match payload {
+        FramePayload::ControllerMessage(ControllerMessage::JoinResponse { assigned_id }) if *assigned_id == MASTER_ID || *assigned_id == u16::MAX => {
+            Some("the join response assigns a reserved ID")
+        },
+        FramePayload::ProtocolMessage(ProtocolMessage::AcknowledgedBatch { message_ids }) if message_ids.len() > MAX_ACK_BATCH => {
+            Some("the acknowledgement batch carries too many message IDs")
+        },
+        FramePayload::Batch(_) if in_batch => Some("a batch is nested in another batch"),
+        FramePayload::Batch(payloads) => payloads.iter().find_map(|payload| payload_violation(payload, true)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_frames_pass() {
+        Frame::join_response(0, 3).debug_assert_invariants();
+        Frame::fade_brightness(1.0, 100).debug_assert_invariants();
+        Frame::new().payload(FramePayload::batch([Frame::tick(1).payload])).debug_assert_invariants();
+    }
+
+    #[test]
+    fn violations() {
+        let mut frame = Frame::tick(0);
+        frame.header.message_id.clear();
+        assert!(frame.invariant_violation().is_some());
+
+        let brightness = Frame::new().client_message(ClientMessage::SetBrightness(1.5));
+        assert!(brightness.invariant_violation().is_some());
+        assert!(Frame::join_response(0, u16::MAX).invariant_violation().is_some());
+
+        let nested = Frame::new().payload(FramePayload::Batch(vec![FramePayload::Batch(Vec::new())]));
+        assert!(nested.invariant_violation().is_some());
+    }
+
+    #[test]
+    fn checked_parts() {
+        let frame = Frame::join_response(0, 3);
+        assert_eq!(frame.clone().try_into_parts_checked(), Ok((frame.header, frame.payload)));
+
+        let invalid = Frame::join_response(0, MASTER_ID);
+        assert_eq!(invalid.validate(), Err(FrameError::InvalidFrame("the join response assigns a reserved ID".to_string())));
+        assert_eq!(invalid.try_into_parts_checked(), Err(FrameError::InvalidFrame("the join response assigns a reserved ID".to_string())));
+    }
+
+    #[cfg(all(debug_assertions, feature = "serde"))]
+    #[test]
+    #[should_panic(expected = "breaks an invariant")]
+    fn encoding_a_malformed_frame_panics() {
+        let _ = Frame::new().client_message(ClientMessage::SetBrightness(f32::NAN)).to_bytes();
+    }
+}
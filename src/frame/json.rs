@@ -0,0 +1,201 @@
+//! A flat JSON representation of frames for WebSocket consumers, available with the `json` feature.
+//!
+//! Serializing a [`Frame`] with `serde_json` directly nests the metadata under `"header"`, which is awkward for frontend code.
+//! The flat representation merges the header fields into the top-level object and names the kind of payload in a `"type"`
+//! field, with the message itself under `"payload"`:
+//!
+//! ```json
+//! {
+//!   "timestamp": "2024-01-20T14:03:11.512+01:00",
+//!   "lifetime": 2,
+//!   "message_id": "V1StGXR8_Z",
+//!   "sender_id": 0,
+//!   "target_id": 3,
+//!   "requires_acknowledgement": true,
+//!   "current_tick": 42,
+//!   "universe": 0,
+//!   "ranging_bytes": [0, 0, 0, 0],
+//!   "type": "ControllerMessage",
+//!   "payload": { "JoinResponse": { "assigned_id": 3 } }
+//! }
+//! ```
+//!
+//! Frames with an `Empty` payload carry `"type": "Empty"` and no `"payload"` field.
+//!
+//! For frame logs, [`Frame::as_json_line`] writes the same object as a line of newline-delimited JSON, which
+//! [`JsonLineReader`] reads back.
+
+use std::io::BufRead;
+
+use serde_derive::{Serialize, Deserialize};
+
+use crate::frame::{
+    Frame,
+    FrameError,
+    FrameHeader,
+    FramePayload,
+    ClientMessage,
+    ControllerMessage,
+    ProtocolMessage,
+};
+
+#[derive(Serialize, Deserialize)]
+struct FlatFrame {
+    #[serde(flatten)]
+    header:  FrameHeader,
+    #[serde(flatten)]
+    payload: FlatPayload,
+}
+
+/// Mirrors `FramePayload`, but tagged with a `"type"` field so it can be flattened into the frame object.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload")]
+enum FlatPayload {
+    ControllerMessage(ControllerMessage),
+    ProtocolMessage(ProtocolMessage),
+    ClientMessage(ClientMessage),
+    Empty,
+    Batch(Vec<FramePayload>),
+}
+
+impl From<FramePayload> for FlatPayload {
+    fn from(payload: FramePayload) -> Self {
+        match payload {
+            FramePayload::ControllerMessage(msg) => Self::ControllerMessage(msg),
+            FramePayload::ProtocolMessage(msg) => Self::ProtocolMessage(msg),
+            FramePayload::ClientMessage(msg) => Self::ClientMessage(msg),
+            FramePayload::Empty => Self::Empty,
+            FramePayload::Batch(payloads) => Self::Batch(payloads),
+        }
+    }
+}
+
+impl From<FlatPayload> for FramePayload {
+    fn from(payload: FlatPayload) -> Self {
+        match payload {
+            FlatPayload::ControllerMessage(msg) => Self::ControllerMessage(msg),
+            FlatPayload::ProtocolMessage(msg) => Self::ProtocolMessage(msg),
+            FlatPayload::ClientMessage(msg) => Self::ClientMessage(msg),
+            FlatPayload::Empty => Self::Empty,
+            FlatPayload::Batch(payloads) => Self::Batch(payloads),
+        }
+    }
+}
+
+impl Frame {
+    /// Encode the frame as a flat JSON object, with the header fields at the top level and a `"type"` discriminant for the payload.
+    pub fn to_flat_json(&self) -> Result<String, FrameError> {
+        let flat = FlatFrame {
+            header:  self.header.clone(),
+            payload: self.payload.clone().into(),
+        };
+
+        serde_json::to_string(&flat).map_err(|_e| FrameError::SerializeError)
+    }
+
+    /// Decode a frame from the flat JSON object produced by [`Frame::to_flat_json`].
+    pub fn from_flat_json(json: &str) -> Result<Self, FrameError> {
+        let flat = serde_json::from_str::<FlatFrame>(json).map_err(|_e| FrameError::SerializeError)?;
+
+        Ok(Self {
+            header:  flat.header,
+            payload: flat.payload.into(),
+            lifetime_overridden: false,
+        })
+    }
+
+    /// Encode the frame as a line of newline-delimited JSON, the flat JSON object followed by a single `\n`.
+    ///
+    /// The object itself never contains a newline, since `serde_json` escapes them inside strings.
+    pub fn as_json_line(&self) -> Result<String, FrameError> {
+        let mut line = self.to_flat_json()?;
+        line.push('\n');
+        Ok(line)
+    }
+}
+
+/// Reads frames from newline-delimited JSON written with [`Frame::as_json_line`], one frame per line.
+///
+/// Blank lines are skipped. A line which is not a valid frame yields `FrameError::SerializeError` without ending the
+/// iteration, so a single garbled line doesn't hide the rest of a log.
+pub struct JsonLineReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+}
+
+impl<R: BufRead> JsonLineReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { lines: reader.lines() }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLineReader<R> {
+    type Item = Result<Frame, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(_e) => return Some(Err(FrameError::SerializeError)),
+            };
+
+            if !line.trim().is_empty() {
+                return Some(Frame::from_flat_json(&line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_json_round_trip() {
+        let frame = Frame::join_response(42, 3);
+
+        let json = frame.to_flat_json().unwrap();
+        assert_eq!(frame, Frame::from_flat_json(&json).unwrap());
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["type"], "ControllerMessage");
+        assert_eq!(value["target_id"], 3);
+        assert_eq!(value["payload"]["JoinResponse"]["assigned_id"], 3);
+        assert!(value.get("header").is_none());
+    }
+
+    #[test]
+    fn flat_json_empty_payload() {
+        let frame = Frame::new().sender_id(5);
+
+        let json = frame.to_flat_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["type"], "Empty");
+        assert!(value.get("payload").is_none());
+        assert_eq!(frame, Frame::from_flat_json(&json).unwrap());
+    }
+
+    #[test]
+    fn json_lines_round_trip() {
+        let log_line = ClientMessage::Log {
+            level:     crate::frame::LogLevel::Info,
+            source_id: 3,
+            message:   "first line\nsecond line".to_string(),
+            tick:      7,
+        };
+        let frames = [Frame::join_response(42, 3), Frame::tick(7), Frame::new().client_message(log_line)];
+
+        let mut log = String::new();
+        for frame in &frames {
+            let line = frame.as_json_line().unwrap();
+            assert_eq!(line.matches('\n').count(), 1);
+            assert!(line.ends_with('\n'));
+            log.push_str(&line);
+        }
+        log.push_str("\nnot a frame\n");
+
+        let read: Vec<_> = JsonLineReader::new(log.as_bytes()).collect();
+        assert_eq!(read.len(), 4);
+        assert_eq!(read[.. 3], frames.map(Ok));
+        assert_eq!(read[3], Err(FrameError::SerializeError));
+    }
+}
@@ -7,7 +7,8 @@
 //! A simple join request can be composed like this using the builder pattern:
 //! 
 //! ```rust
-//! use ledswarm_protocol::frame::{Frame, ControllerMessage};
+//! use ledswarm_protocol::frame::{Frame, ControllerMessage, MASTER_ID};
+//! # let tick = 0;
 //! 
 //! let join_request = Frame::new()
 //!   // Tell the master node that we would like to join the mesh network.
@@ -16,34 +17,199 @@
 //!   // the message until acknowledged by the master node.
 //!   .require_confirmation()
 //!   // Target master nodes only. Every master node has an ID of zero.
-//!   .target_id(0)
+//!   .target_id(MASTER_ID)
 //!   // Add the current time tick to the frame.
-//!   .tick(tick);
+//!   .current_tick(tick);
 //! ```
+//!
+//! On the wire, the bincode serialization of a frame is wrapped like this, using the shared framing of [`crate::wire`]:
+//!
+//! ```text
+//! +------------+-----+---------------+----------------+---------------+
+//! | "LEDswarm" | tag | body length   | bincode body   | ranging bytes |
+//! | 8 bytes    | 1   | 2, u16 LE     | variable       | 4 bytes       |
+//! +------------+-----+---------------+----------------+---------------+
+//! ```
+//!
+//! The body holds the bincode header, its [checksum](checksum) and the bincode payload:
+//!
+//! ```text
+//! +----------------+-----------------+-----------------+
+//! | bincode header | header checksum | bincode payload |
+//! | variable       | 2, u16 LE       | variable        |
+//! +----------------+-----------------+-----------------+
+//! ```
+
+use core::time::Duration;
+use std::fmt;
+#[cfg(feature = "serde")]
+use std::io;
 
+#[cfg(feature = "serde")]
+use bincode::Options;
+use nanoid::nanoid;
+
+#[cfg(feature = "serde")]
 use serde_derive::{Serialize, Deserialize};
 
+#[cfg(feature = "serde")]
+pub mod batch;
+#[cfg(feature = "serde")]
+pub mod bytes;
+pub mod checksum;
+pub mod coalesce;
+#[cfg(feature = "serde")]
+pub mod compact;
+#[cfg(feature = "compression")]
+pub mod compression;
+mod debug;
+pub mod diff;
 pub mod error;
+pub mod filter;
+#[cfg(feature = "heapless")]
+pub mod fixed;
 pub mod header;
+mod invariants;
 pub mod payload;
+#[cfg(feature = "serde")]
+pub mod pool;
+pub mod ranging;
+pub mod raw;
+#[cfg(feature = "serde")]
+pub mod schema;
+#[cfg(feature = "tokio")]
+pub mod stream;
+#[cfg(feature = "crypto")]
+pub mod signature;
+pub mod sync;
+pub mod timeline;
+pub mod vote;
+#[cfg(feature = "json")]
+mod json;
 
-pub use self::header::FrameHeader;
+pub use self::diff::FieldDiff;
+pub use self::filter::FrameFilter;
+#[cfg(feature = "json")]
+pub use self::json::JsonLineReader;
+pub use self::header::{scoped_id, unpack_scoped, FrameHeader, FrameSignature};
+pub use self::ranging::RangingData;
+#[cfg(feature = "serde")]
+pub use self::schema::PayloadSchema;
 pub use self::payload::{
+    AbortReason,
     FramePayload,
+    FrameKind,
     ClientMessage,
     ControllerMessage,
     ProtocolMessage,
     InternalMessage,
+    LogLevel,
+    NackReason,
+    NetworkEvent,
+    NodeInfo,
 };
 pub use self::error::FrameError;
+pub use crate::wire::MAGIC;
+
+use crate::wire::{self, RANGING_LEN};
+
+/// The ID of every master node, which assigns IDs to all other nodes joining its mesh.
+pub const MASTER_ID: u16 = 0;
+
+/// The universe of frames which are meant for every universe, like discovery and join traffic.
+pub const GLOBAL_UNIVERSE: u8 = 0;
+
+/// The number of hops a frame with the given kind of payload is relayed by default, before it is dropped.
+///
+/// | `FrameKind`  | Lifetime | Reason                                                                 |
+/// |--------------|----------|------------------------------------------------------------------------|
+/// | `Protocol`   | 1        | Acknowledgements, ticks and beacons only concern direct neighbors.     |
+/// | `Controller` | 2        | Game commands have to reach controllers which are out of direct range. |
+/// | `Client`     | 2        |                                                                        |
+/// | `Empty`      | 2        | The lifetime of a fresh `FrameHeader`.                                 |
+/// | `Batch`      | 2        | Coalesced frames use the longest lifetime of their parts instead.      |
+///
+/// The payload setters of [`Frame`] apply these, unless the lifetime was set to something else before.
+pub fn default_lifetime(kind: FrameKind) -> u8 {
+    match kind {
+        FrameKind::Protocol => 1,
+        FrameKind::Controller | FrameKind::Client | FrameKind::Empty | FrameKind::Batch => 2,
+    }
+}
+
+/// Fixed time every UWB transmission spends on the air before the first data bit, regardless of the frame length.
+///
+/// This covers the 128-symbol preamble and the 8-symbol start-of-frame delimiter at roughly 1 µs per symbol, plus the PHY header.
+pub const AIRTIME_OVERHEAD: Duration = Duration::from_micros(160);
+
+/// The largest body which is decoded from received bytes, matching the 1023-byte maximum frame length of the DW1000 UWB transceiver.
+///
+/// Length prefixes of strings and vectors are checked against the remaining budget before anything is allocated, so a corrupt or
+/// malicious length produces `FrameError::SerializeError` instead of an attempt to allocate gigabytes.
+pub const MAX_FRAME_SIZE: usize = 1023;
+
+/// Format tag following the magic string of frames whose body is encoded with the default `bincode` configuration.
+///
+/// The tag is checked before decoding, so data recorded with an encoding this version does not know about produces
+/// `FrameError::UnsupportedEncoding` instead of a generic deserialization failure.
+pub const ENCODING_BINCODE: u8 = 1;
+
+/// Format tag of frames in the compact encoding of [`Frame::serialize_compact`], which leaves out default header fields.
+pub const ENCODING_COMPACT: u8 = 2;
+
+/// The magic string, the format tag and the little-endian `u16` length of the body, which precede the body of every encoded frame.
+const PREFIX_LEN: usize = MAGIC.len() + 1 + 2;
+
+/// How the bytes of an encoded frame are distributed over its parts, see [`Frame::cost_bytes_breakdown`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ByteBreakdown {
+    /// The "LEDswarm" magic string.
+    pub magic:   usize,
+    /// The encoding format tag following the magic string.
+    pub tag:     usize,
+    /// The length prefix of the body.
+    pub length:  usize,
+    /// The serialized frame header.
+    pub header:  usize,
+    /// The checksum of the header.
+    pub header_checksum: usize,
+    /// The serialized payload, including its variant discriminants.
+    pub payload: usize,
+    /// The trailing ranging bytes.
+    pub ranging: usize,
+}
+
+impl ByteBreakdown {
+    /// The sum of all parts, which equals `Frame::encoded_len`.
+    pub fn total(&self) -> usize {
+        self.magic + self.tag + self.length + self.header + self.header_checksum + self.payload + self.ranging
+    }
+}
 
 // A container for a single frame of data from the UWB mesh.
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Frame {
     /// Metadata about the frame, such as the sender and target IDs, the universe number, and the current tick.
     pub header:  FrameHeader,
     /// The actual payload of the frame, which can be a game-level command or an internal network command.
     pub payload: FramePayload,
+    /// Whether the lifetime was set with [`Frame::lifetime`], so the payload setters keep it. This is not encoded and
+    /// not compared.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    lifetime_overridden: bool,
+}
+
+impl PartialEq for Frame {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header && self.payload == other.payload
+    }
+}
+
+impl fmt::Debug for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frame").field("header", &self.header).field("payload", &self.payload).finish()
+    }
 }
 
 impl Frame {
@@ -51,6 +217,7 @@ impl Frame {
         Self {
             header: FrameHeader::new(),
             payload: FramePayload::Empty,
+            lifetime_overridden: false,
         }
     }
 
@@ -58,7 +225,7 @@ impl Frame {
         Self::new()
             .message(ControllerMessage::JoinRequest)
             .require_confirmation()
-            .target_id(0)
+            .target_id(MASTER_ID)
             .current_tick(tick)
     }
 
@@ -66,38 +233,232 @@ impl Frame {
         Self::new()
             .message(ControllerMessage::JoinResponse { assigned_id })
             .require_confirmation()
+            .sender_id(MASTER_ID)
             .target_id(assigned_id)
             .current_tick(tick)
     }
 
+    /// Broadcast a discovery request to learn which masters and peers are within range.
+    pub fn discover(tick: u16) -> Self {
+        Self::new()
+            .message(ControllerMessage::Discover)
+            .current_tick(tick)
+    }
+
+    /// Answer a `Discover` broadcast from a node which has not joined the mesh yet.
+    pub fn discover_reply(tick: u16, node_id: u16, is_master: bool, universe: u8, load: u8) -> Self {
+        Self::new()
+            .message(ControllerMessage::DiscoverReply { node_id, is_master, universe, load })
+            .sender_id(node_id)
+            .target_id(u16::MAX)
+            .universe(universe)
+            .current_tick(tick)
+    }
+
+    /// Smoothly ramp the brightness of the targeted controllers to `target`, clamped to the range between 0.0 and 1.0.
+    pub fn fade_brightness(target: f32, duration_ms: u32) -> Self {
+        let target = if target.is_nan() { 0.0 } else { target.clamp(0.0, 1.0) };
+
+        Self::new()
+            .message(ControllerMessage::FadeBrightness { target, duration_ms })
+    }
+
+    /// Ask all controllers for their current global brightness.
+    pub fn brightness_query() -> Self {
+        Self::new()
+            .message(ControllerMessage::BrightnessQuery)
+            .sender_id(MASTER_ID)
+    }
+
+    /// Report the current global brightness of this controller to the master.
+    pub fn brightness_report(brightness: f32) -> Self {
+        Self::new()
+            .message(ControllerMessage::BrightnessReport { brightness })
+            .target_id(MASTER_ID)
+    }
+
+    /// Start a two-way ranging exchange with the node `target`.
+    pub fn ranging_request(target: u16) -> Self {
+        Self::new()
+            .message(ControllerMessage::RangingRequest)
+            .target_id(target)
+    }
+
+    /// Answer the `RangingRequest` of `target`, which was received `t_reply` UWB time units before this response is sent.
+    pub fn ranging_response(target: u16, t_reply: u64) -> Self {
+        Self::new()
+            .message(ControllerMessage::RangingResponse { t_reply })
+            .target_id(target)
+    }
+
+    /// Ask all nodes whether their players are ready for the next round.
+    pub fn ready_check(tick: u16) -> Self {
+        Self::new()
+            .message(ControllerMessage::ReadyCheck)
+            .sender_id(MASTER_ID)
+            .current_tick(tick)
+    }
+
+    /// Tell the master that the player of this node is ready, or not ready yet.
+    pub fn ready(tick: u16, ready: bool) -> Self {
+        Self::new()
+            .message(if ready { ControllerMessage::Ready } else { ControllerMessage::NotReady })
+            .target_id(MASTER_ID)
+            .current_tick(tick)
+    }
+
+    /// Broadcast an emote of the player of this node to every other node, with one of the IDs in [`payload::emote`].
+    pub fn emote(tick: u16, emote: u8) -> Self {
+        Self::new()
+            .message(ControllerMessage::Emote { emote })
+            .current_tick(tick)
+    }
+
+    /// Broadcast the vote of the player of this node for `choice` on `topic`.
+    pub fn vote(topic: u8, choice: u8) -> Self {
+        Self::new().message(ControllerMessage::Vote { topic, choice })
+    }
+
+    /// Move the controller `controller_id` into `universe`, asking for confirmation since the controller stops
+    /// listening to its current universe once it switched.
+    pub fn set_universe(controller_id: u16, universe: u8) -> Self {
+        Self::new()
+            .message(ControllerMessage::SetUniverse { universe })
+            .require_confirmation()
+            .sender_id(MASTER_ID)
+            .target_id(controller_id)
+    }
+
+    /// Tell the client `target` to leave its master and join `new_master_id` in `universe` instead.
+    pub fn redirect(target: u16, new_master_id: u16, universe: u8) -> Self {
+        Self::new()
+            .message(ControllerMessage::Redirect { new_master_id, universe })
+            .sender_id(MASTER_ID)
+            .target_id(target)
+    }
+
+    /// Assign a controller to a team and set its team color, targeting the controller directly.
+    pub fn team_assign(controller_id: u16, team: u8, color: (u8, u8, u8)) -> Self {
+        Self::new()
+            .message(ControllerMessage::TeamAssign { controller_id, team, color })
+            .sender_id(MASTER_ID)
+            .target_id(controller_id)
+    }
+
     pub fn tick(tick: u16) -> Self {
         Self::new()
             .protocol_message(ProtocolMessage::Tick(tick))
             .current_tick(tick)
     }
 
+    /// A control frame carrying the given protocol message, like an acknowledgement or a beacon.
+    ///
+    /// Control frames should always carry a `ProtocolMessage`, which tells the receiver what to do with the frame and
+    /// keeps its short default lifetime. `FramePayload::Empty` is only the placeholder of a frame under construction.
+    pub fn control(msg: ProtocolMessage) -> Self {
+        Self::new().protocol_message(msg)
+    }
+
+    /// Announce the protocol version of this build and the given features to all neighbors.
+    pub fn hello(capabilities: crate::mesh::caps::Capabilities) -> Self {
+        Self::control(capabilities.hello())
+    }
+
+    /// Announce to all neighbors that this node is alive and will beacon again within `interval_ticks`.
+    pub fn keep_alive(tick: u16, interval_ticks: u16) -> Self {
+        Self::new()
+            .protocol_message(ProtocolMessage::KeepAlive { interval_ticks })
+            .current_tick(tick)
+    }
+
+    /// Start a new frame just like this one, to send it again to a different target or on a different tick.
+    ///
+    /// The payload and the addressing fields of the header are copied, while the new frame gets a fresh message ID
+    /// and timestamp, so it isn't discarded as a duplicate. Fields which only make sense for the original transmission,
+    /// like the remaining lifetime, the ranging bytes, the visited nodes, the deadline and a piggybacked acknowledgement,
+    /// are reset to their defaults.
+    pub fn builder_from(&self) -> Frame {
+        let mut frame = Frame::new();
+
+        frame.header.sender_id = self.header.sender_id;
+        frame.header.target_id = self.header.target_id;
+        frame.header.requires_acknowledgement = self.header.requires_acknowledgement;
+        frame.header.ack_timeout_ticks = self.header.ack_timeout_ticks;
+        frame.header.current_tick = self.header.current_tick;
+        frame.header.universe = self.header.universe;
+
+        frame.payload(self.payload.clone())
+    }
+
+    /// A copy of this frame which is stamped with the given tick, leaving everything else untouched.
+    pub fn clone_with_tick(&self, tick: u16) -> Frame {
+        let mut frame = self.clone();
+        frame.restamp(tick);
+        frame
+    }
+
+    /// Stamp this frame with the given tick in place, to re-broadcast a cached frame on the current tick.
+    pub fn restamp(&mut self, tick: u16) {
+        self.header.current_tick = tick;
+    }
+
     /// Set a game-level command as the message payload of the frame.
-    pub fn message(mut self, msg: ControllerMessage) -> Self {
-        self.payload = FramePayload::ControllerMessage(msg);
-        self
+    pub fn message(self, msg: ControllerMessage) -> Self {
+        self.payload(FramePayload::ControllerMessage(msg))
     }
 
     /// Set an internal network command as the message payload of the frame.
-    pub fn protocol_message(mut self, protocol_msg: ProtocolMessage) -> Self {
-        self.payload = FramePayload::ProtocolMessage(protocol_msg);
-        self
+    pub fn protocol_message(self, protocol_msg: ProtocolMessage) -> Self {
+        self.payload(FramePayload::ProtocolMessage(protocol_msg))
     }
 
-    pub fn client_message(mut self, msg: ClientMessage) -> Self {
-        self.payload = FramePayload::ClientMessage(msg);
+    pub fn client_message(self, msg: ClientMessage) -> Self {
+        self.payload(FramePayload::ClientMessage(msg))
+    }
+
+    /// Set the payload of the frame, switching to the [`default_lifetime`] of its kind unless the lifetime was set with
+    /// [`Frame::lifetime`] or changed from the default of the previous payload.
+    pub fn payload(mut self, payload: FramePayload) -> Self {
+        self.replace_payload(payload, false);
         self
     }
 
+    /// Swap the payload of the frame in place, adjusting the lifetime like [`Frame::payload`]. With `refresh_id`, the
+    /// frame also gets a new message ID, which is required when the result is sent as a new message, since receivers
+    /// drop frames with an ID they already saw from the same sender.
+    pub fn replace_payload(&mut self, payload: FramePayload, refresh_id: bool) {
+        if !self.lifetime_overridden && self.header.lifetime == default_lifetime(self.payload.kind()) {
+            self.header.lifetime = default_lifetime(payload.kind());
+        }
+
+        self.payload = payload;
+        if refresh_id {
+            self.header.message_id = nanoid!(10);
+        }
+    }
+
+    /// Set the number of hops the frame is relayed before it is dropped. Prefer [`Frame::ttl`], which is the same with a clearer name.
     pub fn lifetime(mut self, lifetime: u8) -> Self {
         self.header.lifetime = lifetime;
+        self.lifetime_overridden = true;
         self
     }
 
+    /// Set the number of hops the frame may still travel before it is dropped. A TTL of zero makes the frame undeliverable,
+    /// see [`Frame::try_ttl`] to reject it.
+    pub fn ttl(self, hops: u8) -> Self {
+        self.lifetime(hops)
+    }
+
+    /// Set the hop TTL like [`Frame::ttl`], but return `FrameError::ZeroTtl` instead of building an undeliverable frame.
+    pub fn try_ttl(self, hops: u8) -> Result<Self, FrameError> {
+        if hops == 0 {
+            Err(FrameError::ZeroTtl)
+        } else {
+            Ok(self.ttl(hops))
+        }
+    }
+
     pub fn sender_id(mut self, id: u16) -> Self {
         self.header.sender_id = id;
         self
@@ -122,35 +483,1188 @@ impl Frame {
         self.header.requires_acknowledgement = true;
         self
     }
+
+    /// Ask the `AckManager` to wait the given number of ticks for an acknowledgement before retrying, for example a
+    /// short timeout for frames which are worthless when late. Has no effect without `require_confirmation`.
+    pub fn ack_timeout(mut self, ticks: u16) -> Self {
+        self.header.ack_timeout_ticks = Some(ticks);
+        self
+    }
+
+    /// Only deliver the frame up to and including the given tick, after which relays drop it, see
+    /// [`Frame::is_past_deadline`].
+    pub fn deadline(mut self, tick: u16) -> Self {
+        self.header.deadline_tick = Some(tick);
+        self
+    }
+
+    /// Whether the deadline of the frame passed at the tick `now`, assuming both lie less than half a tick cycle
+    /// apart. Frames without a deadline never expire.
+    pub fn is_past_deadline(&self, now: u16) -> bool {
+        self.header.deadline_tick.is_some_and(|deadline| crate::mesh::tick::offset(deadline, now) > 0)
+    }
+
+    /// Modify the header with a closure, as an escape hatch for fields which don't have a builder method of their own.
+    ///
+    /// The closure has full access to the header, so it can also break invariants the other builder methods uphold,
+    /// like the lifetime defaults of [`Frame::payload`].
+    pub fn map_header(mut self, f: impl FnOnce(&mut FrameHeader)) -> Self {
+        f(&mut self.header);
+        self
+    }
+
+    /// The game-level command carried by this frame, if any.
+    pub fn as_controller_message(&self) -> Option<&ControllerMessage> {
+        match &self.payload {
+            FramePayload::ControllerMessage(msg) => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// The internal network command carried by this frame, if any.
+    pub fn as_protocol_message(&self) -> Option<&ProtocolMessage> {
+        match &self.payload {
+            FramePayload::ProtocolMessage(msg) => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// Whether the payload of this frame is a `ProtocolMessage::Encrypted`, which only its target can read. Relays still
+    /// forward it, since all fields needed for routing are in the header.
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self.payload, FramePayload::ProtocolMessage(ProtocolMessage::Encrypted { .. }))
+    }
+
+    /// The GUI client message carried by this frame, if any.
+    pub fn as_client_message(&self) -> Option<&ClientMessage> {
+        match &self.payload {
+            FramePayload::ClientMessage(msg) => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// The GUI client message to forward to the dashboard for this frame, if its payload has a client representation.
+    ///
+    /// Client messages are returned as they are, while game commands like `FadeBrightness`, `TeamAssign`, `TeamScore`,
+    /// `Emote` and `BrightnessReport` are mapped to their client counterparts. Everything else, like joins and protocol messages, returns `None`.
+    pub fn to_client_message(&self) -> Option<ClientMessage> {
+        match &self.payload {
+            FramePayload::ClientMessage(msg) => Some(msg.clone()),
+            FramePayload::ControllerMessage(msg) => match *msg {
+                ControllerMessage::FadeBrightness { target, duration_ms } => Some(ClientMessage::FadeBrightness { target, duration_ms }),
+                ControllerMessage::TeamAssign { controller_id, team, color } => Some(ClientMessage::TeamAssign { controller_id, team, color }),
+                ControllerMessage::TeamScore { team, score } => Some(ClientMessage::TeamScore { team, score }),
+                ControllerMessage::Emote { emote } => Some(ClientMessage::Emote { controller_id: self.header.sender_id, emote }),
+                ControllerMessage::BrightnessReport { brightness } => Some(ClientMessage::BrightnessReport { controller_id: self.header.sender_id, brightness }),
+                _ => None,
+            },
+            FramePayload::ProtocolMessage(_) | FramePayload::Empty | FramePayload::Batch(_) => None,
+        }
+    }
+
+    /// Whether the payload is safe to act on again if the frame is delivered more than once, for example after a
+    /// retransmission whose acknowledgement got lost.
+    ///
+    /// Payloads which set a state to an absolute value, like `SetBrightness`, `TeamScore` or `Tick`, are idempotent.
+    /// Payloads which trigger an action or change state relative to the current one, like `JoinRequest`, `StartRound`
+    /// or `Redirect`, are not, so receivers have to discard duplicates of those by their message ID.
+    pub fn is_idempotent(&self) -> bool {
+        self.payload.is_idempotent()
+    }
+
+    /// Whether this frame was sent by a master node.
+    pub fn sender_is_master(&self) -> bool {
+        self.header.sender_id == MASTER_ID
+    }
+
+    /// Whether this frame is addressed to a master node, which is not the case for broadcasts.
+    pub fn target_is_master(&self) -> bool {
+        self.header.target_id == Some(MASTER_ID)
+    }
+
+    /// Whether a node in `my_universe` should act on this frame, which is the case if the frame belongs to the same
+    /// universe or to the [`GLOBAL_UNIVERSE`]. Frames of other universes belong to another game and should be dropped.
+    pub fn is_same_universe(&self, my_universe: u8) -> bool {
+        self.header.universe == my_universe || self.header.universe == GLOBAL_UNIVERSE
+    }
+
+    /// Acknowledge a previously received message along with this frame, instead of sending a dedicated `Acknowledged` frame.
+    pub fn also_acks(mut self, message_id: impl Into<String>) -> Self {
+        self.header.acked_message_id = Some(message_id.into());
+        self
+    }
+
+    /// The discriminant the payload is encoded with on the wire, which identifies its [`FrameKind`] right after the header
+    /// checksum.
+    pub fn kind_byte(&self) -> u8 {
+        match self.payload {
+            FramePayload::ControllerMessage(_) => 0,
+            FramePayload::ProtocolMessage(_) => 1,
+            FramePayload::ClientMessage(_) => 2,
+            FramePayload::Empty => 3,
+            FramePayload::Batch(_) => 4,
+        }
+    }
+
+    /// The number of bytes this frame occupies on the wire, including the magic string, format tag, length prefix and the trailing ranging bytes.
+    #[cfg(feature = "serde")]
+    pub fn encoded_len(&self) -> Result<usize, FrameError> {
+        Ok(PREFIX_LEN + self.body_len()? as usize + RANGING_LEN)
+    }
+
+    /// The length of the body in the standard encoding, which must not exceed [`MAX_FRAME_SIZE`] as a whole, although
+    /// the header and the payload are measured separately.
+    #[cfg(feature = "serde")]
+    fn body_len(&self) -> Result<u16, FrameError> {
+        let options = wire::bincode_options();
+        let header_len = options.serialized_size(&self.header).map_err(|_e| FrameError::SerializeError)?;
+        let payload_len = options.serialized_size(&self.payload).map_err(|_e| FrameError::SerializeError)?;
+
+        let body_len = header_len + checksum::HEADER_CHECKSUM_LEN as u64 + payload_len;
+        if body_len > MAX_FRAME_SIZE as u64 {
+            return Err(FrameError::SerializeError);
+        }
+
+        Ok(body_len as u16)
+    }
+
+    /// Write the body in the standard encoding: the header, its checksum and the payload.
+    #[cfg(feature = "serde")]
+    fn write_body<W: io::Write>(&self, writer: W) -> Result<(), FrameError> {
+        let options = wire::bincode_options();
+
+        let mut checked = checksum::ChecksumWriter::new(writer);
+        options.serialize_into(&mut checked, &self.header).map_err(|_e| FrameError::SerializeError)?;
+        let (mut writer, header_checksum) = checked.finish();
+        writer.write_all(&header_checksum.to_le_bytes()).map_err(|_e| FrameError::SerializeError)?;
+        options.serialize_into(writer, &self.payload).map_err(|_e| FrameError::SerializeError)
+    }
+
+    /// Serialize the frame with the fields which relaying nodes mutate in transit zeroed, for computing and verifying signatures.
+    ///
+    /// The `lifetime` is decremented on every hop, the `ranging_bytes` are rewritten by each transmitting UWB module and
+    /// relaying nodes add themselves to the `visited` nodes, so none of them may be covered by a signature computed at
+    /// the source. The signature itself is left out as well, everything else is serialized as is.
+    #[cfg(feature = "serde")]
+    pub fn canonical_bytes(&self) -> Result<Vec<u8>, FrameError> {
+        let mut canonical = self.clone();
+        canonical.header.lifetime = 0;
+        canonical.header.ranging_bytes = [0; 4];
+        canonical.header.signature = None;
+        canonical.header.visited.clear();
+
+        wire::bincode_options().serialize(&canonical).map_err(|_e| FrameError::SerializeError)
+    }
+
+    /// Whether this frame answers `request`, either by acknowledging its message ID, in an `Acknowledged` or
+    /// `AcknowledgedBatch` message or piggybacked with [`Frame::also_acks`], or by being the reply message to it:
+    ///
+    /// | Request           | Reply                    |
+    /// |-------------------|--------------------------|
+    /// | `JoinRequest`     | `JoinResponse`           |
+    /// | `Discover`        | `DiscoverReply`          |
+    /// | `ReadyCheck`      | `Ready` or `NotReady`    |
+    /// | `BrightnessQuery` | `BrightnessReport`       |
+    /// | `RangingRequest`  | `RangingResponse`        |
+    ///
+    /// Reply messages don't carry the ID of their request, so they are matched by addressing only: the reply must be
+    /// sent to the requesting node, by the requested node if the request had a target. A joining node has no ID yet, so
+    /// `JoinResponse`s are only checked for their sender. Two concurrent requests of the same kind from the same node
+    /// can't be told apart this way.
+    pub fn is_reply_to(&self, request: &Frame) -> bool {
+        let addressed_back = self.header.target_id.is_none_or(|target_id| target_id == request.header.sender_id);
+        let from_target = request.header.target_id.is_none_or(|target_id| target_id == self.header.sender_id);
+        if !from_target {
+            return false;
+        }
+
+        let request_id = &request.header.message_id;
+        let acknowledged = match self.as_protocol_message() {
+            Some(ProtocolMessage::Acknowledged { message_id }) => message_id == request_id,
+            Some(ProtocolMessage::AcknowledgedBatch { message_ids }) => message_ids.contains(request_id),
+            _ => false,
+        } || self.header.acked_message_id.as_ref() == Some(request_id);
+        if acknowledged {
+            return addressed_back;
+        }
+
+        let (Some(request_msg), Some(reply_msg)) = (request.as_controller_message(), self.as_controller_message()) else {
+            return false;
+        };
+
+        match (request_msg, reply_msg) {
+            (ControllerMessage::JoinRequest, ControllerMessage::JoinResponse { .. }) => true,
+            (ControllerMessage::Discover, ControllerMessage::DiscoverReply { .. })
+            | (ControllerMessage::ReadyCheck, ControllerMessage::Ready | ControllerMessage::NotReady)
+            | (ControllerMessage::BrightnessQuery, ControllerMessage::BrightnessReport { .. })
+            | (ControllerMessage::RangingRequest, ControllerMessage::RangingResponse { .. }) => {
+                self.header.target_id == Some(request.header.sender_id)
+            },
+            _ => false,
+        }
+    }
+
+    /// The time the frame was sent by its original sender, see [`FrameHeader::origin_timestamp`].
+    pub fn origin_timestamp(&self) -> Result<chrono::DateTime<chrono::FixedOffset>, FrameError> {
+        self.header.origin_timestamp()
+    }
+
+    /// Whether `other` is another copy of the same transmission as this frame, for example relayed over a different path.
+    ///
+    /// Message IDs are only unique per sender, so frames are duplicates if both their `sender_id` and `message_id` match,
+    /// regardless of any other field, including those mutated in transit like the lifetime and the ranging bytes.
+    pub fn is_duplicate_of(&self, other: &Frame) -> bool {
+        self.header.sender_id == other.header.sender_id && self.header.message_id == other.header.message_id
+    }
+
+    /// Whether the node relayed this frame before, according to the `visited` nodes recorded in its header.
+    pub fn has_visited(&self, id: u16) -> bool {
+        self.header.visited.contains(&id)
+    }
+
+    /// Record that the node is relaying this frame, dropping the oldest entry if [`MAX_VISITED`](header::MAX_VISITED)
+    /// nodes are recorded already.
+    pub fn mark_visited(&mut self, id: u16) {
+        if self.has_visited(id) {
+            return;
+        }
+        if self.header.visited.len() >= header::MAX_VISITED {
+            self.header.visited.remove(0);
+        }
+        self.header.visited.push(id);
+    }
+
+    /// Whether two frames are equal apart from the fields relaying nodes mutate in transit, see [`Frame::canonical_bytes`].
+    pub fn semantically_eq(&self, other: &Frame) -> bool {
+        let normalize = |frame: &Frame| {
+            let mut frame = frame.clone();
+            frame.header.lifetime = 0;
+            frame.header.ranging_bytes = [0; 4];
+            frame.header.visited.clear();
+            frame
+        };
+
+        normalize(self) == normalize(other)
+    }
+
+    /// Break the encoded size of this frame down into its parts, to find out where the bytes go when optimizing the wire format.
+    #[cfg(feature = "serde")]
+    pub fn cost_bytes_breakdown(&self) -> ByteBreakdown {
+        ByteBreakdown {
+            magic:   MAGIC.len(),
+            tag:     1,
+            length:  2,
+            header:  wire::bincode_options().serialized_size(&self.header).unwrap_or(0) as usize,
+            header_checksum: checksum::HEADER_CHECKSUM_LEN,
+            payload: wire::bincode_options().serialized_size(&self.payload).unwrap_or(0) as usize,
+            ranging: RANGING_LEN,
+        }
+    }
+
+    /// Estimate how long transmitting this frame occupies the channel at the given data rate, including [`AIRTIME_OVERHEAD`].
+    ///
+    /// Fails like [`Frame::encoded_len`] for frames which can not be encoded, since they are never sent, and with
+    /// `FrameError::ZeroBitrate` if `bitrate_bps` is zero.
+    #[cfg(feature = "serde")]
+    pub fn estimate_airtime(&self, bitrate_bps: u32) -> Result<Duration, FrameError> {
+        if bitrate_bps == 0 {
+            return Err(FrameError::ZeroBitrate);
+        }
+
+        let bits = self.encoded_len()? as u64 * 8;
+        let nanos = (bits * 1_000_000_000).div_ceil(bitrate_bps as u64);
+
+        Ok(AIRTIME_OVERHEAD + Duration::from_nanos(nanos))
+    }
+
+    /// The largest payload, as serialized including its variant discriminants, which fits into a frame of at most `mtu`
+    /// bytes, for sizing the chunks of a payload split over several frames.
+    ///
+    /// The overhead taken off consists of the magic string, the format tag and the length prefix, the header of a fresh
+    /// `FrameHeader` with a full-precision timestamp, its checksum, and the trailing ranging bytes. Setting a target,
+    /// acknowledging a message, signing or relaying the frame grows the header beyond that, so frames using those need
+    /// their own budget from [`Frame::cost_bytes_breakdown`]. Zero if not even the overhead fits.
+    #[cfg(feature = "serde")]
+    pub fn max_payload_bytes(mtu: usize) -> usize {
+        let header = FrameHeader {
+            timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Nanos, false),
+            ..FrameHeader::new()
+        };
+        let header_len = wire::bincode_options().serialized_size(&header).unwrap_or(0) as usize;
+
+        mtu.saturating_sub(PREFIX_LEN + header_len + checksum::HEADER_CHECKSUM_LEN + RANGING_LEN)
+    }
+
+    /// Encode the frame for the wire without consuming it, like the `TryFrom<Frame> for Vec<u8>` conversion does.
+    ///
+    /// The buffer is allocated with the exact encoded length up front, so it never has to grow while the parts are appended.
+    #[cfg(feature = "serde")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FrameError> {
+        let mut bytes = Vec::with_capacity(self.encoded_len().unwrap_or(MAX_FRAME_SIZE));
+        self.write_to(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    /// Append the encoded frame to `buffer`, for example to pack several frames into one reused buffer.
+    ///
+    /// The bincode body is serialized straight into the buffer. If encoding fails, `buffer` is left as it was.
+    #[cfg(feature = "serde")]
+    pub fn write_to(&self, buffer: &mut Vec<u8>) -> Result<(), FrameError> {
+        let start = buffer.len();
+        let result = self.append_encoded(buffer);
+        if result.is_err() {
+            buffer.truncate(start);
+        }
+
+        result
+    }
+
+    #[cfg(feature = "serde")]
+    fn append_encoded(&self, buffer: &mut Vec<u8>) -> Result<(), FrameError> {
+        self.debug_assert_invariants();
+
+        let body_len = self.body_len()?;
+
+        buffer.reserve(PREFIX_LEN + body_len as usize + RANGING_LEN);
+        buffer.extend_from_slice(MAGIC);
+        buffer.push(ENCODING_BINCODE);
+        buffer.extend_from_slice(&body_len.to_le_bytes());
+        self.write_body(&mut *buffer)?;
+        buffer.extend_from_slice(&self.header.ranging_bytes);
+
+        Ok(())
+    }
+
+    /// Encode the frame for the wire, additionally returning its length if it exceeds `warn_threshold` bytes, so bloated
+    /// frames can be flagged before they saturate the link.
+    ///
+    /// Fails like [`Frame::to_bytes`] if the frame can not be encoded, for example because it is longer than
+    /// [`MAX_FRAME_SIZE`].
+    #[cfg(feature = "serde")]
+    pub fn serialize_with_warning(&self, warn_threshold: usize) -> Result<(Vec<u8>, Option<usize>), FrameError> {
+        let bytes = self.to_bytes()?;
+        let oversized = (bytes.len() > warn_threshold).then_some(bytes.len());
+
+        Ok((bytes, oversized))
+    }
+
+    /// Whether the buffer starts with the magic string, to reject foreign UWB traffic before attempting to decode it.
+    ///
+    /// Buffers shorter than the magic string are rejected. A `true` result does not guarantee the frame decodes.
+    pub fn verify_magic_only(bytes: &[u8]) -> bool {
+        wire::has_magic(bytes)
+    }
+
+    /// Decode a frame from a buffer which holds exactly one frame.
+    ///
+    /// The frame is parsed up to the end announced by its length prefix. Bytes following the frame are ignored,
+    /// unless `strict` is set, in which case they are rejected with `FrameError::TrailingBytes`.
+    #[cfg(feature = "serde")]
+    pub fn from_bytes(bytes: &[u8], strict: bool) -> Result<Frame, FrameError> {
+        let (frame, consumed) = Self::try_from_prefix(bytes)?;
+        if strict && consumed < bytes.len() {
+            return Err(FrameError::TrailingBytes { count: bytes.len() - consumed });
+        }
+
+        Ok(frame)
+    }
+
+    /// Decode the frame at the start of a buffer, returning it along with the number of bytes it took up.
+    ///
+    /// Buffers holding several concatenated frames can be processed by decoding again from the returned offset.
+    #[cfg(feature = "serde")]
+    pub fn try_from_prefix(bytes: &[u8]) -> Result<(Frame, usize), FrameError> {
+        let framed = framed_body(bytes)?;
+        let decoded = match framed.encoding {
+            ENCODING_COMPACT => compact::decode_body(framed.body),
+            _ => decode_body(framed.body),
+        };
+
+        decoded.map(|mut packet| {
+            // Put the ranging data following the body into the message, so it can be restored if the message is serialized again.
+            packet.header.ranging_bytes = framed.ranging_bytes;
+            (packet, framed.len)
+        })
+    }
+
+    /// Decode only the header of the frame at the start of a buffer, leaving the payload untouched.
+    ///
+    /// The header is encoded before the payload, so relays can decide where to forward a frame without paying for
+    /// decoding its payload. The buffer still has to hold the complete frame, and the result equals the header of
+    /// [`Frame::try_from_prefix`], ranging bytes included. Compact frames are decoded completely, since their default
+    /// header fields depend on the payload.
+    #[cfg(feature = "serde")]
+    pub fn peek_header(bytes: &[u8]) -> Result<FrameHeader, FrameError> {
+        let framed = framed_body(bytes)?;
+
+        let mut header = match framed.encoding {
+            ENCODING_COMPACT => compact::decode_body(framed.body)?.header,
+            _ => decode_header(framed.body)?.0,
+        };
+        header.ranging_bytes = framed.ranging_bytes;
+
+        Ok(header)
+    }
+
+    /// Extract every frame from a capture buffer, collecting errors instead of stopping at the first one.
+    ///
+    /// After a frame failed to decode, or if the buffer holds bytes which don't start with the magic string, scanning
+    /// resumes at the next occurrence of the magic string. Every undecodable stretch of bytes produces one error.
+    #[cfg(feature = "serde")]
+    pub fn decode_many(bytes: &[u8]) -> (Vec<Frame>, Vec<FrameError>) {
+        let (mut frames, mut errors) = (Vec::new(), Vec::new());
+        let next_magic = |from: usize| {
+            bytes[from ..]
+                .windows(MAGIC.len())
+                .position(|window| window == MAGIC)
+                .map(|offset| from + offset)
+        };
+
+        let mut offset = 0;
+        while offset < bytes.len() {
+            if !bytes[offset ..].starts_with(MAGIC) {
+                errors.push(wire::check_magic(&bytes[offset ..]).unwrap_err().into());
+                match next_magic(offset) {
+                    Some(next) => offset = next,
+                    None => break,
+                }
+            }
+
+            match Self::try_from_prefix(&bytes[offset ..]) {
+                Ok((frame, consumed)) => {
+                    frames.push(frame);
+                    offset += consumed;
+                },
+                Err(e) => {
+                    errors.push(e);
+                    offset = next_magic(offset + 1).unwrap_or(bytes.len());
+                },
+            }
+        }
+
+        (frames, errors)
+    }
+
+    /// Decode a single frame from the start of a byte iterator, returning the iterator positioned right after the frame.
+    ///
+    /// Only as many bytes as announced by the length prefix are consumed, so the remaining bytes, like those of a following
+    /// frame, stay available through the returned iterator.
+    #[cfg(feature = "serde")]
+    pub fn try_from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Result<(Frame, I::IntoIter), FrameError> {
+        let mut iter = iter.into_iter();
+
+        let mut bytes: Vec<u8> = iter.by_ref().take(PREFIX_LEN).collect();
+        let body_len = frame_len(&bytes)? - PREFIX_LEN;
+        bytes.extend(iter.by_ref().take(body_len));
+
+        Ok((Frame::try_from(bytes.as_slice())?, iter))
+    }
 }
 
-impl From<Frame> for Vec<u8> {
-    fn from(packet: Frame) -> Vec<u8> {
-        let mut buffer = Vec::new();
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        buffer.extend_from_slice("LEDswarm".as_bytes());
-        buffer.append(&mut bincode::serialize(&packet).unwrap());
-        buffer.append(&mut packet.header.ranging_bytes.to_vec());
+#[cfg(feature = "serde")]
+impl TryFrom<Frame> for Vec<u8> {
+    type Error = FrameError;
 
-        buffer
+    fn try_from(frame: Frame) -> Result<Vec<u8>, FrameError> {
+        frame.to_bytes()
     }
 }
 
+#[cfg(feature = "serde")]
 impl TryFrom<Vec<u8>> for Frame {
     type Error = FrameError;
 
     fn try_from(vec: Vec<u8>) -> Result<Self, FrameError> {
-        if core::str::from_utf8(&vec[0 .. 8]).unwrap() == "LEDswarm" {
-            match bincode::deserialize::<Self>(&vec["LEDswarm".len() .. vec.len() - 4]) {
-                Ok(mut packet) => {
-                    // Extract the last four bytes, which are ranging data, and put them into the message, so they can be restored if the message is serialized again.
-                    packet.header.ranging_bytes = [vec[vec.len() - 4], vec[vec.len() - 3], vec[vec.len() - 2], vec[vec.len() - 1]];
-                    Ok(packet)
-                },
-                Err(_e) => Err(FrameError::SerializeError),
-            }
-        } else {
-            Err(FrameError::NoMagicString(String::from_utf8(vec[0 .. 7].to_vec()).unwrap()))
+        Self::try_from(vec.as_slice())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<&[u8]> for Frame {
+    type Error = FrameError;
+
+    /// Decode a frame, ignoring any bytes following it, see [`Frame::from_bytes`].
+    fn try_from(vec: &[u8]) -> Result<Self, FrameError> {
+        Self::from_bytes(vec, false)
+    }
+}
+
+/// The parts of an encoded frame, as split up by [`framed_body`].
+struct Framed<'a> {
+    encoding:      u8,
+    body:          &'a [u8],
+    ranging_bytes: [u8; RANGING_LEN],
+    /// The number of bytes the frame takes up, including the framing.
+    len:           usize,
+}
+
+/// Check the framing and encoding of the frame at the start of `bytes`, splitting it into its parts.
+fn framed_body(bytes: &[u8]) -> Result<Framed<'_>, FrameError> {
+    let frame_len = frame_len(bytes)?;
+    if bytes.len() < frame_len {
+        return Err(FrameError::TooShort { expected: frame_len, actual: bytes.len() });
+    }
+
+    let (tagged, ranging_bytes) = wire::parse_framed(&bytes[.. frame_len])?;
+    match tagged[0] {
+        encoding @ (ENCODING_BINCODE | ENCODING_COMPACT) => Ok(Framed {
+            encoding,
+            body: &tagged[PREFIX_LEN - MAGIC.len() ..],
+            ranging_bytes,
+            len: frame_len,
+        }),
+        unknown => Err(FrameError::UnsupportedEncoding(unknown)),
+    }
+}
+
+/// Decode a body in the standard encoding.
+#[cfg(feature = "serde")]
+fn decode_body(body: &[u8]) -> Result<Frame, FrameError> {
+    let (header, payload) = decode_header(body)?;
+    let payload = wire::deserialize::<FramePayload>(payload).map_err(|_e| FrameError::SerializeError)?;
+
+    Ok(Frame { header, payload, lifetime_overridden: false })
+}
+
+/// Decode the header of a body in the standard encoding and check it against its checksum, returning it along with the
+/// still encoded payload.
+#[cfg(feature = "serde")]
+fn decode_header(body: &[u8]) -> Result<(FrameHeader, &[u8]), FrameError> {
+    let mut rest = body;
+    let header: FrameHeader = wire::bincode_options().deserialize_from(&mut rest).map_err(|_e| FrameError::SerializeError)?;
+    let header_len = body.len() - rest.len();
+
+    if rest.len() < checksum::HEADER_CHECKSUM_LEN {
+        return Err(FrameError::SerializeError);
+    }
+    let (header_checksum, payload) = rest.split_at(checksum::HEADER_CHECKSUM_LEN);
+    if u16::from_le_bytes([header_checksum[0], header_checksum[1]]) != checksum::crc16(&body[.. header_len]) {
+        return Err(FrameError::BadHeaderChecksum);
+    }
+
+    Ok((header, payload))
+}
+
+/// Check the prefix of an encoded frame, returning the total length of the frame as announced by its length prefix.
+fn frame_len(bytes: &[u8]) -> Result<usize, FrameError> {
+    if bytes.is_empty() {
+        return Err(FrameError::EmptyBuffer);
+    }
+
+    wire::check_magic(bytes)?;
+    if bytes.len() < PREFIX_LEN {
+        return Err(FrameError::TooShort { expected: PREFIX_LEN, actual: bytes.len() });
+    }
+
+    let body_len = u16::from_le_bytes([bytes[PREFIX_LEN - 2], bytes[PREFIX_LEN - 1]]) as usize;
+    if body_len > MAX_FRAME_SIZE {
+        return Err(FrameError::SerializeError);
+    }
+
+    Ok(PREFIX_LEN + body_len + RANGING_LEN)
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    /// The body of the frame in the standard encoding, assembled by hand.
+    fn standard_body(frame: &Frame) -> Vec<u8> {
+        let mut body = bincode::serialize(&frame.header).unwrap();
+        body.extend_from_slice(&checksum::crc16(&body).to_le_bytes());
+        body.extend_from_slice(&bincode::serialize(&frame.payload).unwrap());
+        body
+    }
+
+    #[test]
+    fn serialize_deserialize_discover() {
+        let frame = Frame::discover(7);
+
+        let encoded = frame.to_bytes().unwrap();
+        assert_eq!(frame, Frame::try_from(encoded).unwrap());
+    }
+
+    #[test]
+    fn serialize_deserialize_discover_reply() {
+        let frame = Frame::discover_reply(7, 0, true, 2, 10);
+
+        let encoded = frame.to_bytes().unwrap();
+        assert_eq!(frame, Frame::try_from(encoded).unwrap());
+    }
+
+    #[test]
+    fn serialize_deserialize_fade_brightness() {
+        let frame = Frame::fade_brightness(0.75, 2000);
+
+        let encoded = frame.to_bytes().unwrap();
+        assert_eq!(frame, Frame::try_from(encoded).unwrap());
+
+        #[cfg(feature = "json")]
+        assert_eq!(frame, Frame::from_flat_json(&frame.to_flat_json().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn lifetime_defaults_by_kind() {
+        assert_eq!(Frame::tick(3).header.lifetime, default_lifetime(FrameKind::Protocol));
+        assert_eq!(Frame::join_request(3).header.lifetime, default_lifetime(FrameKind::Controller));
+        assert_ne!(Frame::tick(3).header.lifetime, Frame::join_request(3).header.lifetime);
+
+        // Explicit lifetimes survive changing the payload, in either order.
+        assert_eq!(Frame::new().lifetime(6).protocol_message(ProtocolMessage::Tick(3)).header.lifetime, 6);
+        assert_eq!(Frame::tick(3).lifetime(6).header.lifetime, 6);
+        // Even when they equal the default of the previous payload.
+        assert_eq!(Frame::new().lifetime(2).protocol_message(ProtocolMessage::Tick(3)).header.lifetime, 2);
+        assert_eq!(Frame::tick(3).lifetime(1).message(ControllerMessage::JoinRequest).header.lifetime, 1);
+    }
+
+    #[test]
+    fn ttl() {
+        let frame = Frame::join_request(0).ttl(4);
+        assert_eq!(frame.header.ttl_remaining(), 4);
+        assert_eq!(frame.header.lifetime, 4);
+
+        assert_eq!(Frame::join_request(0).try_ttl(3).map(|frame| frame.header.ttl_remaining()), Ok(3));
+        assert_eq!(Frame::join_request(0).try_ttl(0), Err(FrameError::ZeroTtl));
+    }
+
+    #[test]
+    fn map_header_in_builder_chain() {
+        let frame = Frame::join_request(3)
+            .map_header(|header| header.message_id = "V1StGXR8_Z".to_string())
+            .universe(4);
+
+        assert_eq!(frame.header.message_id, "V1StGXR8_Z");
+        assert_eq!(frame.header.universe, 4);
+        assert_eq!(frame.header.current_tick, 3);
+    }
+
+    #[test]
+    fn clone_with_tick_only_changes_tick() {
+        let frame = Frame::discover_reply(7, 3, true, 2, 10).require_confirmation();
+
+        let restamped = frame.clone_with_tick(900);
+        let diff = frame.diff(&restamped);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].field, "header.current_tick");
+        assert_eq!(restamped.header.current_tick, 900);
+
+        let mut in_place = frame.clone();
+        in_place.restamp(900);
+        assert_eq!(in_place, restamped);
+    }
+
+    #[test]
+    fn serialize_deserialize_lobby() {
+        for frame in [Frame::ready_check(4), Frame::ready(5, true).sender_id(3), Frame::ready(5, false).sender_id(3)] {
+            let encoded = frame.to_bytes().unwrap();
+            assert_eq!(frame, Frame::try_from(encoded).unwrap());
         }
+
+        assert_eq!(Frame::ready(5, false).as_controller_message(), Some(&ControllerMessage::NotReady));
+    }
+
+    #[test]
+    fn serialize_deserialize_redirect() {
+        let frame = Frame::redirect(5, 2, 1);
+        assert_eq!(frame.header.target_id, Some(5));
+
+        let encoded = frame.to_bytes().unwrap();
+        assert_eq!(frame, Frame::try_from(encoded).unwrap());
+    }
+
+    #[test]
+    fn serialize_deserialize_teams() {
+        let frame = Frame::team_assign(3, 1, (0, 255, 0));
+        assert_eq!(frame.header.target_id, Some(3));
+
+        let encoded = frame.to_bytes().unwrap();
+        assert_eq!(frame, Frame::try_from(encoded).unwrap());
+
+        let frame = Frame::new().message(ControllerMessage::TeamScore { team: 1, score: 12 });
+        let encoded = frame.to_bytes().unwrap();
+        assert_eq!(frame, Frame::try_from(encoded).unwrap());
+    }
+
+    #[test]
+    fn fade_brightness_is_clamped() {
+        let expected = |target| FramePayload::ControllerMessage(ControllerMessage::FadeBrightness { target, duration_ms: 10 });
+
+        assert_eq!(Frame::fade_brightness(1.5, 10).payload, expected(1.0));
+        assert_eq!(Frame::fade_brightness(-0.5, 10).payload, expected(0.0));
+        assert_eq!(Frame::fade_brightness(f32::NAN, 10).payload, expected(0.0));
+    }
+
+    #[test]
+    fn estimate_airtime_of_tick() {
+        let mut frame = Frame::tick(5);
+        frame.header.timestamp = "now".to_string();
+        frame.header.message_id = "0123456789".to_string();
+
+        assert_eq!(frame.encoded_len().unwrap(), 80);
+        assert_eq!(frame.to_bytes().unwrap().len(), 80);
+        // 80 bytes are 640 bits, which take 640 µs at 1 Mbps.
+        assert_eq!(frame.estimate_airtime(1_000_000), Ok(AIRTIME_OVERHEAD + Duration::from_micros(640)));
+        assert_eq!(frame.estimate_airtime(0), Err(FrameError::ZeroBitrate));
+
+        frame.header.message_id = "x".repeat(MAX_FRAME_SIZE);
+        assert_eq!(frame.estimate_airtime(1_000_000), Err(FrameError::SerializeError));
+    }
+
+    #[test]
+    fn max_payload_bytes_leaves_room_for_the_overhead() {
+        let mut frame = Frame::new();
+        frame.header.timestamp = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Nanos, false);
+        let breakdown = frame.cost_bytes_breakdown();
+
+        assert_eq!(Frame::max_payload_bytes(MAX_FRAME_SIZE), MAX_FRAME_SIZE - (breakdown.total() - breakdown.payload));
+        assert_eq!(Frame::max_payload_bytes(127) + breakdown.total() - breakdown.payload, 127);
+        assert_eq!(Frame::max_payload_bytes(20), 0);
+    }
+
+    #[test]
+    fn cost_bytes_breakdown_sums_to_encoded_len() {
+        let frame = Frame::tick(5);
+        let breakdown = frame.cost_bytes_breakdown();
+
+        assert_eq!(breakdown.total(), frame.encoded_len().unwrap());
+        assert_eq!(breakdown.magic, 8);
+        assert_eq!(breakdown.ranging, 4);
+        // The RFC 3339 timestamp alone outweighs the whole tick payload.
+        assert!(frame.header.timestamp.len() > breakdown.payload);
+    }
+
+    #[test]
+    fn canonical_bytes_ignore_transit_fields() {
+        let sent = Frame::join_request(3).lifetime(4);
+        let mut relayed = sent.clone().lifetime(2);
+        relayed.header.ranging_bytes = [9, 8, 7, 6];
+
+        assert_eq!(sent.canonical_bytes().unwrap(), relayed.canonical_bytes().unwrap());
+        assert_ne!(sent.canonical_bytes().unwrap(), sent.clone().sender_id(5).canonical_bytes().unwrap());
+    }
+
+    #[test]
+    fn typed_payload_accessors() {
+        let controller = Frame::join_request(0);
+        let protocol = Frame::tick(0);
+        let client = Frame::new().client_message(ClientMessage::EndRound);
+        let empty = Frame::new();
+
+        assert_eq!(controller.as_controller_message(), Some(&ControllerMessage::JoinRequest));
+        assert_eq!(controller.as_protocol_message(), None);
+        assert_eq!(controller.as_client_message(), None);
+
+        assert_eq!(protocol.as_protocol_message(), Some(&ProtocolMessage::Tick(0)));
+        assert_eq!(protocol.as_controller_message(), None);
+
+        assert_eq!(client.as_client_message(), Some(&ClientMessage::EndRound));
+        assert_eq!(client.as_protocol_message(), None);
+
+        assert_eq!(empty.as_controller_message(), None);
+        assert_eq!(empty.as_protocol_message(), None);
+        assert_eq!(empty.as_client_message(), None);
+    }
+
+    #[test]
+    fn replace_payload_in_place() {
+        let mut frame = Frame::join_request(4).sender_id(9).lifetime(5);
+        let message_id = frame.header.message_id.clone();
+
+        frame.replace_payload(FramePayload::ProtocolMessage(ProtocolMessage::Tick(3)), false);
+        assert_eq!(frame.as_protocol_message(), Some(&ProtocolMessage::Tick(3)));
+        assert_eq!(frame.header.message_id, message_id);
+        assert_eq!(frame.header.lifetime, 5);
+
+        frame.replace_payload(Frame::join_request(5).payload, true);
+        assert_ne!(frame.header.message_id, message_id);
+        assert_eq!(frame.header.message_id.len(), 10);
+        assert!(!frame.is_duplicate_of(&Frame::join_request(5).sender_id(9).map_header(|header| header.message_id = message_id.clone())));
+    }
+
+    #[test]
+    fn deadline_across_tick_wrap() {
+        let frame = Frame::tick(u16::MAX - 2).deadline(1);
+
+        assert!(!frame.is_past_deadline(u16::MAX - 2));
+        assert!(!frame.is_past_deadline(u16::MAX));
+        assert!(!frame.is_past_deadline(1));
+        assert!(frame.is_past_deadline(2));
+        assert!(frame.is_past_deadline(100));
+        assert!(!Frame::tick(0).is_past_deadline(u16::MAX / 2));
+        assert_eq!(frame.builder_from().header.deadline_tick, None);
+    }
+
+    #[test]
+    fn builder_from_refreshes_message_id() {
+        let original = Frame::join_request(4).sender_id(9).universe(2).lifetime(5).also_acks("V1StGXR8_Z");
+        let copy = original.builder_from().target_id(3);
+
+        assert_eq!(copy.payload, original.payload);
+        assert_ne!(copy.header.message_id, original.header.message_id);
+        assert_eq!(copy.header.sender_id, 9);
+        assert_eq!(copy.header.universe, 2);
+        assert_eq!(copy.header.current_tick, 4);
+        assert_eq!(copy.header.target_id, Some(3));
+        assert_eq!(copy.header.lifetime, default_lifetime(FrameKind::Controller));
+        assert_eq!(copy.header.acked_message_id, None);
+    }
+
+    #[test]
+    fn master_checks() {
+        let request = Frame::join_request(0).sender_id(u16::MAX);
+        let response = Frame::join_response(0, 3);
+
+        assert!(request.target_is_master());
+        assert!(!request.sender_is_master());
+        assert!(response.sender_is_master());
+        assert!(!response.target_is_master());
+        assert!(!Frame::tick(0).target_is_master());
+    }
+
+    #[test]
+    fn absurd_length_prefix_is_rejected() {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(b"LEDswarm");
+        buffer.push(ENCODING_BINCODE);
+        buffer.extend_from_slice(&12u16.to_le_bytes());
+        // The timestamp string is the first field of the header, declare it to be an exabyte long.
+        buffer.extend_from_slice(&(1u64 << 60).to_le_bytes());
+        buffer.extend_from_slice(b"2024");
+        buffer.extend_from_slice(&[0; 4]);
+
+        assert_eq!(Frame::try_from(buffer), Err(FrameError::SerializeError));
+    }
+
+    #[test]
+    fn header_and_payload_only_fit_on_their_own() {
+        let mut frame = Frame::new().client_message(ClientMessage::StartRound("x".repeat(MAX_FRAME_SIZE / 2)));
+        frame.header.message_id = "x".repeat(MAX_FRAME_SIZE / 2);
+
+        let options = wire::bincode_options();
+        assert!(options.serialized_size(&frame.header).is_ok());
+        assert!(options.serialized_size(&frame.payload).is_ok());
+        assert_eq!(frame.encoded_len(), Err(FrameError::SerializeError));
+        assert_eq!(frame.to_bytes(), Err(FrameError::SerializeError));
+        assert_eq!(Frame::coalesce(&[frame.clone(), frame]), None);
+    }
+
+    #[test]
+    fn oversized_body_is_rejected() {
+        let mut frame = Frame::tick(0);
+        frame.header.message_id = "x".repeat(MAX_FRAME_SIZE);
+        assert_eq!(frame.to_bytes(), Err(FrameError::SerializeError));
+        assert_eq!(Vec::try_from(frame.clone()), Err(FrameError::SerializeError));
+
+        // Encoded by a sender which does not enforce the limit.
+        let body = bincode::serialize(&frame).unwrap();
+        let mut buffer = MAGIC.to_vec();
+        buffer.push(ENCODING_BINCODE);
+        buffer.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        buffer.extend_from_slice(&body);
+        buffer.extend_from_slice(&frame.header.ranging_bytes);
+
+        assert_eq!(Frame::try_from(buffer), Err(FrameError::SerializeError));
+    }
+
+    #[test]
+    fn decode_tagged_buffer() {
+        let frame = Frame::tick(5);
+
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(b"LEDswarm");
+        buffer.push(ENCODING_BINCODE);
+        let mut body = standard_body(&frame);
+        buffer.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        buffer.append(&mut body);
+        buffer.extend_from_slice(&frame.header.ranging_bytes);
+
+        assert_eq!(buffer, frame.to_bytes().unwrap());
+        assert_eq!(frame, Frame::try_from(buffer.clone()).unwrap());
+
+        buffer[8] = 0xEE;
+        assert_eq!(Frame::try_from(buffer), Err(FrameError::UnsupportedEncoding(0xEE)));
+    }
+
+    #[test]
+    fn try_from_iter_extracts_first_frame() {
+        let first = Frame::join_request(1);
+        let second = Frame::tick(2);
+
+        let bytes = first.to_bytes().unwrap().into_iter().chain(second.to_bytes().unwrap());
+        let (decoded, rest) = Frame::try_from_iter(bytes).unwrap();
+        assert_eq!(decoded, first);
+
+        let (decoded, mut rest) = Frame::try_from_iter(rest).unwrap();
+        assert_eq!(decoded, second);
+        assert_eq!(rest.next(), None);
+    }
+
+    #[test]
+    fn truncated_frame_is_rejected() {
+        let mut bytes = Frame::tick(2).to_bytes().unwrap();
+        let len = bytes.len();
+        bytes.truncate(len - 1);
+
+        assert_eq!(Frame::try_from(bytes.as_slice()), Err(FrameError::TooShort { expected: len, actual: len - 1 }));
+        assert_eq!(Frame::try_from(&bytes[.. 9]), Err(FrameError::TooShort { expected: 11, actual: 9 }));
+        assert_eq!(Frame::try_from_iter(bytes).err(), Some(FrameError::TooShort { expected: len, actual: len - 1 }));
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let frame = Frame::tick(5);
+        let mut encoded = frame.to_bytes().unwrap();
+
+        assert_eq!(Frame::from_bytes(&encoded, true), Ok(frame.clone()));
+
+        encoded.extend_from_slice(b"garbage");
+        assert_eq!(Frame::from_bytes(&encoded, false), Ok(frame.clone()));
+        assert_eq!(Frame::from_bytes(&encoded, true), Err(FrameError::TrailingBytes { count: 7 }));
+        assert_eq!(Frame::try_from(encoded), Ok(frame));
+    }
+
+    #[test]
+    fn concatenated_frames() {
+        let frames = [Frame::tick(1), Frame::discover(2), Frame::keep_alive(3, 50)];
+        let buffer: Vec<u8> = frames.iter().flat_map(|frame| frame.to_bytes().unwrap()).collect();
+
+        let mut offset = 0;
+        let mut decoded = Vec::new();
+        while offset < buffer.len() {
+            let (frame, consumed) = Frame::try_from_prefix(&buffer[offset ..]).unwrap();
+            decoded.push(frame);
+            offset += consumed;
+        }
+
+        assert_eq!(offset, buffer.len());
+        assert_eq!(decoded, frames);
+    }
+
+    #[test]
+    fn serialize_with_warning() {
+        let frame = Frame::tick(5);
+        let (bytes, warning) = frame.serialize_with_warning(128).unwrap();
+        assert_eq!(warning, None);
+        assert_eq!(bytes, frame.to_bytes().unwrap());
+
+        let frame = Frame::new().client_message(ClientMessage::StartRound("x".repeat(200)));
+        let (bytes, warning) = frame.serialize_with_warning(128).unwrap();
+        assert_eq!(warning, Some(bytes.len()));
+        assert!(bytes.len() > 200);
+
+        let frame = Frame::new().client_message(ClientMessage::StartRound("x".repeat(MAX_FRAME_SIZE)));
+        assert_eq!(frame.serialize_with_warning(128), Err(FrameError::SerializeError));
+    }
+
+    #[test]
+    fn preallocated_bytes_are_unchanged() {
+        let frame = Frame::discover_reply(7, 3, true, 2, 10).also_acks("V1StGXR8_Z");
+        let body = standard_body(&frame);
+
+        let mut expected = MAGIC.to_vec();
+        expected.push(ENCODING_BINCODE);
+        expected.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        expected.extend_from_slice(&body);
+        expected.extend_from_slice(&frame.header.ranging_bytes);
+
+        let bytes = frame.to_bytes().unwrap();
+        assert_eq!(bytes, expected);
+        assert_eq!(bytes.capacity(), bytes.len());
+
+        let mut buffer = b"prefix".to_vec();
+        frame.write_to(&mut buffer).unwrap();
+        assert_eq!(&buffer[6 ..], &expected[..]);
+    }
+
+    #[test]
+    fn empty_short_and_foreign_buffers() {
+        assert_eq!(Frame::try_from(Vec::new()), Err(FrameError::EmptyBuffer));
+        assert_eq!(Frame::try_from_iter(Vec::new()).err(), Some(FrameError::EmptyBuffer));
+        assert_eq!(Frame::try_from(&b"LED"[..]), Err(FrameError::TooShort { expected: 8, actual: 3 }));
+        assert_eq!(Frame::try_from(&b"HTTP/1.1"[..]), Err(FrameError::NoMagicString("HTTP/1.1".to_string())));
+    }
+
+    #[test]
+    fn idempotent_payloads() {
+        assert!(Frame::new().client_message(ClientMessage::SetBrightness(0.5)).is_idempotent());
+        assert!(Frame::tick(4).is_idempotent());
+        assert!(Frame::new().message(ControllerMessage::TeamScore { team: 0, score: 3 }).is_idempotent());
+        assert!(Frame::new().is_idempotent());
+
+        assert!(!Frame::join_request(4).is_idempotent());
+        assert!(!Frame::redirect(3, 1, 0).is_idempotent());
+        assert!(!Frame::new().client_message(ClientMessage::StartRound("territory".to_string())).is_idempotent());
+    }
+
+    #[test]
+    fn to_client_message() {
+        let frame = Frame::new().client_message(ClientMessage::SetBrightness(0.25));
+        assert_eq!(frame.to_client_message(), Some(ClientMessage::SetBrightness(0.25)));
+
+        assert_eq!(
+            Frame::fade_brightness(0.5, 100).to_client_message(),
+            Some(ClientMessage::FadeBrightness { target: 0.5, duration_ms: 100 }),
+        );
+        assert_eq!(
+            Frame::team_assign(3, 1, (0, 255, 0)).to_client_message(),
+            Some(ClientMessage::TeamAssign { controller_id: 3, team: 1, color: (0, 255, 0) }),
+        );
+        assert_eq!(Frame::join_request(0).to_client_message(), None);
+        assert_eq!(Frame::tick(0).to_client_message(), None);
+    }
+
+    #[test]
+    fn visited_nodes_are_capped() {
+        let mut frame = Frame::tick(3);
+        for id in 1 ..= header::MAX_VISITED as u16 {
+            frame.mark_visited(id);
+        }
+        frame.mark_visited(1);
+        assert_eq!(frame.header.visited.len(), header::MAX_VISITED);
+        assert!(frame.has_visited(1));
+
+        frame.mark_visited(100);
+        assert_eq!(frame.header.visited.len(), header::MAX_VISITED);
+        assert!(frame.has_visited(100));
+        assert!(!frame.has_visited(1));
+        assert_eq!(Frame::try_from(&frame.to_bytes().unwrap()[..]), Ok(frame.clone()));
+        assert!(frame.semantically_eq(&Frame { header: FrameHeader { visited: Vec::new(), ..frame.header.clone() }, ..frame.clone() }));
+    }
+
+    #[test]
+    fn replies_match_their_request() {
+        let request = Frame::join_request(0).sender_id(7);
+        let ack = Frame::control(ProtocolMessage::Acknowledged { message_id: request.header.message_id.clone() })
+            .sender_id(MASTER_ID)
+            .target_id(7);
+        assert!(ack.is_reply_to(&request));
+        assert!(Frame::tick(3).sender_id(MASTER_ID).also_acks(request.header.message_id.clone()).is_reply_to(&request));
+        assert!(Frame::join_response(0, 8).is_reply_to(&request));
+
+        // Acknowledging another message, or sent by another node than the request was for.
+        assert!(!Frame::control(ProtocolMessage::Acknowledged { message_id: "V1StGXR8_Z".to_string() }).sender_id(MASTER_ID).is_reply_to(&request));
+        assert!(!ack.clone().sender_id(4).is_reply_to(&request));
+        assert!(!request.is_reply_to(&ack));
+
+        let query = Frame::brightness_query().target_id(4);
+        assert!(Frame::brightness_report(0.5).sender_id(4).is_reply_to(&query));
+        assert!(!Frame::brightness_report(0.5).sender_id(5).is_reply_to(&query));
+        assert!(!Frame::ranging_response(MASTER_ID, 10).sender_id(4).is_reply_to(&query));
+    }
+
+    #[test]
+    fn relayed_copies_are_duplicates() {
+        let original = Frame::join_request(3).sender_id(4).lifetime(3);
+        let mut relayed = original.clone().lifetime(1);
+        relayed.header.ranging_bytes = [1, 2, 3, 4];
+
+        assert!(relayed.is_duplicate_of(&original));
+        assert!(!Frame::join_request(3).sender_id(4).is_duplicate_of(&original));
+
+        let same_id_other_sender = original.clone().sender_id(5);
+        assert!(!same_id_other_sender.is_duplicate_of(&original));
+    }
+
+    #[test]
+    fn decode_many_resynchronizes_after_junk() {
+        let (first, second) = (Frame::tick(1), Frame::discover(2));
+
+        let mut capture = first.to_bytes().unwrap();
+        capture.extend_from_slice(b"junk junk junk");
+        capture.extend_from_slice(&second.to_bytes().unwrap());
+
+        let (frames, errors) = Frame::decode_many(&capture);
+        assert_eq!(frames, [first.clone(), second]);
+        assert_eq!(errors, [FrameError::NoMagicString("junk jun".to_string())]);
+
+        // A frame cut off by the end of the capture.
+        let truncated = first.to_bytes().unwrap();
+        let (frames, errors) = Frame::decode_many(&truncated[.. truncated.len() - 1]);
+        assert!(frames.is_empty());
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn peek_header_matches_full_decode() {
+        let frame = Frame::join_response(7, 3).also_acks("request").universe(2).ranging(0x0102_0304_u32);
+        let bytes = frame.to_bytes().unwrap();
+
+        assert_eq!(Frame::peek_header(&bytes), Ok(Frame::try_from(&bytes[..]).unwrap().header));
+        assert_eq!(Frame::peek_header(&bytes[.. 20]), Err(FrameError::TooShort { expected: bytes.len(), actual: 20 }));
+    }
+
+    #[test]
+    fn kind_byte_follows_the_header() {
+        for frame in [Frame::join_request(0), Frame::tick(0), Frame::new().client_message(ClientMessage::EndRound), Frame::new()] {
+            let body = standard_body(&frame);
+            let header_len = wire::bincode_options().serialized_size(&frame.header).unwrap() as usize;
+
+            assert_eq!(body[header_len + checksum::HEADER_CHECKSUM_LEN], frame.kind_byte());
+        }
+    }
+
+    #[test]
+    fn universe_isolation() {
+        assert!(Frame::tick(0).universe(1).is_same_universe(1));
+        assert!(!Frame::tick(0).universe(2).is_same_universe(1));
+        assert!(!Frame::tick(0).universe(2).is_same_universe(GLOBAL_UNIVERSE));
+        assert!(Frame::tick(0).universe(GLOBAL_UNIVERSE).is_same_universe(1));
+    }
+
+    #[test]
+    fn ack_only_control_frame() {
+        let ack = Frame::control(ProtocolMessage::Acknowledged { message_id: "V1StGXR8_Z".to_string() }).target_id(4);
+
+        assert_eq!(ack.payload.kind(), FrameKind::Protocol);
+        assert_eq!(ack.header.lifetime, default_lifetime(FrameKind::Protocol));
+        assert_eq!(ack.header.target_id, Some(4));
+        assert_eq!(Frame::try_from(&ack.to_bytes().unwrap()[..]).unwrap(), ack);
+    }
+
+    #[test]
+    fn emote_round_trip() {
+        let frame = Frame::emote(9, payload::emote::WAVE).sender_id(3);
+
+        assert_eq!(frame.header.target_id, None);
+        assert_eq!(Frame::try_from(&frame.to_bytes().unwrap()[..]).unwrap(), frame);
+        assert_eq!(frame.to_client_message(), Some(ClientMessage::Emote { controller_id: 3, emote: payload::emote::WAVE }));
+        assert!(!frame.is_idempotent());
+    }
+
+    #[test]
+    fn verify_magic_only() {
+        let bytes = Frame::tick(1).to_bytes().unwrap();
+
+        assert!(Frame::verify_magic_only(&bytes));
+        assert!(Frame::verify_magic_only(MAGIC));
+        assert!(!Frame::verify_magic_only(b"LEDSWARM and more"));
+        assert!(!Frame::verify_magic_only(&bytes[.. MAGIC.len() - 1]));
+        assert!(!Frame::verify_magic_only(&[]));
+    }
+
+    #[test]
+    fn set_universe_round_trip() {
+        let frame = Frame::set_universe(5, 2).universe(1);
+
+        assert_eq!(frame.header.target_id, Some(5));
+        assert_eq!(Frame::try_from(&frame.to_bytes().unwrap()[..]), Ok(frame.clone()));
+        assert_eq!(Frame::deserialize_compact(&frame.serialize_compact().unwrap()), Ok(frame));
+    }
+
+    #[test]
+    fn brightness_read_back() {
+        let query = Frame::brightness_query();
+        assert_eq!(Frame::try_from(&query.to_bytes().unwrap()[..]), Ok(query));
+
+        let report = Frame::brightness_report(0.75).sender_id(6);
+        assert_eq!(Frame::try_from(&report.to_bytes().unwrap()[..]), Ok(report.clone()));
+        assert_eq!(report.to_client_message(), Some(ClientMessage::BrightnessReport { controller_id: 6, brightness: 0.75 }));
     }
 }
@@ -1,9 +1,104 @@
+#[cfg(feature = "serde")]
 use serde_derive::{Serialize, Deserialize};
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ControllerMessage {
     JoinRequest,
     JoinResponse {
         assigned_id: u16,
     },
-}
\ No newline at end of file
+    /// Broadcast by a freshly powered node to enumerate the masters and peers within range before sending a `JoinRequest`.
+    Discover,
+    /// Response to a `Discover` broadcast, describing the replying node so the joining node can pick the best master.
+    DiscoverReply {
+        /// The assigned ID of the replying node.
+        node_id: u16,
+        /// Whether the replying node is a master node accepting `JoinRequest`s.
+        is_master: bool,
+        /// The universe the replying node is currently part of.
+        universe: u8,
+        /// How busy the replying node is, from 0 (idle) to 255 (fully loaded).
+        load: u8,
+    },
+    /// Smoothly ramp the global brightness from its current value to `target` (between 0.0 and 1.0). Firmware should
+    /// interpolate linearly over `duration_ms` instead of jumping to the target, which causes visible flicker.
+    FadeBrightness {
+        target: f32,
+        duration_ms: u32,
+    },
+    /// Sent by a master to a client to move it over to another master, for example to balance the load between masters.
+    ///
+    /// The client hands over by saying `Bye` to its current master and sending a `JoinRequest` targeting `new_master_id`
+    /// in `universe`, joining just like it would after a fresh `Discover`.
+    Redirect {
+        new_master_id: u16,
+        universe: u8,
+    },
+    /// Broadcast by the master before starting a round, asking every node whether its player is ready.
+    ///
+    /// Each node answers with `Ready` or `NotReady`, and answers again whenever its readiness changes. The master
+    /// reports the collected answers to the GUI in `ClientMessage::LobbyState`, and sends `StartRound` only once every
+    /// node in the mesh answered `Ready`.
+    ReadyCheck,
+    /// The player of the sending node is ready for the round to start.
+    Ready,
+    /// The player of the sending node is not ready yet.
+    NotReady,
+    /// Assign a controller to a team in team-based game modes like `Territory`, sent by the master to the controller
+    /// when a round starts or teams are rebalanced.
+    ///
+    /// Teams are numbered consecutively starting at 0, so a round with 4 teams uses 0 to 3. The team number
+    /// [`NO_TEAM`](Self::NO_TEAM) removes the controller from its team, for example when it joins in the middle of a round.
+    TeamAssign {
+        controller_id: u16,
+        team: u8,
+        /// The color the controller should show for its team, as RGB.
+        color: (u8, u8, u8),
+    },
+    /// The current score of a team, broadcast by the master whenever it changes.
+    TeamScore {
+        team: u8,
+        score: i32,
+    },
+    /// A player expression like waving or taunting, broadcast by a controller while idle or in the lobby, so the
+    /// other controllers can play a short animation. The ID is one of the constants in [`emote`](super::emote).
+    Emote {
+        emote: u8,
+    },
+    /// Sent by a master to move a controller into another universe, relaying a `ClientMessage::SetUniverse` from the GUI.
+    ///
+    /// After accepting, the controller sets the `universe` of every frame it sends to the new universe, and ignores
+    /// frames of its former universe from then on.
+    SetUniverse {
+        universe: u8,
+    },
+    /// Sent by a master to ask controllers for their current global brightness, answered with a `BrightnessReport`.
+    BrightnessQuery,
+    /// The current global brightness of the sending controller as a percentage between 0.0 and 1.0, sent to the master
+    /// in response to a `BrightnessQuery`.
+    BrightnessReport {
+        brightness: f32,
+    },
+    /// Start a two-way ranging exchange with the target, which answers with a `RangingResponse` right away.
+    ///
+    /// The sender notes the UWB timestamp at which this request left its antenna, see
+    /// [`TwoWayRanging`](crate::frame::ranging::TwoWayRanging).
+    RangingRequest,
+    /// The answer to a `RangingRequest`.
+    RangingResponse {
+        /// The time between receiving the request and sending this response, in UWB time units of the responder.
+        t_reply: u64,
+    },
+    /// The player's vote for `choice` on a decision `topic` defined by the game, like the next mode. Every node counts
+    /// the votes it receives in a [`VoteTally`](crate::frame::vote::VoteTally).
+    Vote {
+        topic:  u8,
+        choice: u8,
+    },
+}
+
+impl ControllerMessage {
+    /// The team number of controllers which are not part of any team.
+    pub const NO_TEAM: u8 = u8::MAX;
+}
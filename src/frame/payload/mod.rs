@@ -1,16 +1,21 @@
 //! Definitions for different types of frame payloads to distinguish between high-level and low-level transactions.
 
+use core::fmt;
+
+#[cfg(feature = "serde")]
 use serde_derive::{Serialize, Deserialize};
 
 mod controller;
 mod protocol;
 
 pub use self::controller::ControllerMessage;
-pub use self::protocol::ProtocolMessage;
+pub use self::protocol::{AbortReason, ProtocolMessage};
 
 use crate::frame::Frame;
+use crate::packet::GameMode;
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FramePayload {
     /// High-level transactions like joining the mesh, sending game commands or other messages.
     ControllerMessage(ControllerMessage),
@@ -20,26 +25,612 @@ pub enum FramePayload {
     ClientMessage(ClientMessage),
     /// A message meant for internal use only, not to be sent over the network.
     // InternalMessage(InternalMessage),
-    /// Empty payload, mostly used for control messages which don't carry any data.
+    /// No payload, the placeholder of a frame fresh from [`Frame::new`] before a message is set.
+    ///
+    /// Control frames like acknowledgements should carry a `ProtocolMessage` instead, see [`Frame::control`]. Both
+    /// variants cost the same four-byte discriminant on the wire.
     Empty,
+    /// Several payloads for the same target coalesced into a single frame, see [`Frame::coalesce`].
+    Batch(Vec<FramePayload>),
+}
+
+/// The kind of payload carried by a frame, without the message itself.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FrameKind {
+    Controller,
+    Protocol,
+    Client,
+    Empty,
+    Batch,
+}
+
+impl FramePayload {
+    pub fn kind(&self) -> FrameKind {
+        match self {
+            Self::ControllerMessage(_) => FrameKind::Controller,
+            Self::ProtocolMessage(_) => FrameKind::Protocol,
+            Self::ClientMessage(_) => FrameKind::Client,
+            Self::Empty => FrameKind::Empty,
+            Self::Batch(_) => FrameKind::Batch,
+        }
+    }
+
+    /// Whether acting on this payload twice has the same effect as acting on it once, see [`Frame::is_idempotent`].
+    pub fn is_idempotent(&self) -> bool {
+        match self {
+            Self::ControllerMessage(msg) => match msg {
+                // A repeated request could be assigned a second ID.
+                ControllerMessage::JoinRequest => false,
+                // A repeated redirect would make the node leave the master it was just redirected to.
+                ControllerMessage::Redirect { .. } => false,
+                ControllerMessage::JoinResponse { .. }
+                | ControllerMessage::Discover
+                | ControllerMessage::DiscoverReply { .. }
+                | ControllerMessage::FadeBrightness { .. }
+                | ControllerMessage::ReadyCheck
+                | ControllerMessage::Ready
+                | ControllerMessage::NotReady
+                | ControllerMessage::TeamAssign { .. }
+                | ControllerMessage::TeamScore { .. }
+                | ControllerMessage::SetUniverse { .. }
+                | ControllerMessage::BrightnessQuery
+                | ControllerMessage::BrightnessReport { .. }
+                | ControllerMessage::RangingRequest
+                | ControllerMessage::RangingResponse { .. }
+                | ControllerMessage::Vote { .. } => true,
+                // The animation would be played a second time.
+                ControllerMessage::Emote { .. } => false,
+            },
+            Self::ProtocolMessage(msg) => match msg {
+                ProtocolMessage::Acknowledged { .. }
+                | ProtocolMessage::Tick(_)
+                | ProtocolMessage::KeepAlive { .. }
+                | ProtocolMessage::Election { .. }
+                | ProtocolMessage::Coordinator { .. }
+                | ProtocolMessage::ClockDrift { .. }
+                | ProtocolMessage::Hello { .. }
+                | ProtocolMessage::AcknowledgedBatch { .. }
+                | ProtocolMessage::Abort { .. } => true,
+                // Idempotent exactly if the compressed payload is, which can't be told without decompressing it.
+                ProtocolMessage::Compressed { .. } => false,
+                // Likewise, nothing is known about the payload before decrypting it.
+                ProtocolMessage::Encrypted { .. } => false,
+            },
+            Self::ClientMessage(msg) => match msg {
+                // Rounds would be restarted or ended a second time, and log lines would show up twice.
+                ClientMessage::StartRound(_) | ClientMessage::EndRound | ClientMessage::Log { .. } | ClientMessage::Emote { .. } => false,
+                ClientMessage::SetBrightness(_)
+                | ClientMessage::FadeBrightness { .. }
+                | ClientMessage::Error { .. }
+                | ClientMessage::RequestSnapshot
+                | ClientMessage::Snapshot { .. }
+                | ClientMessage::LobbyState { .. }
+                | ClientMessage::TeamAssign { .. }
+                | ClientMessage::TeamScore { .. }
+                | ClientMessage::SetUniverse { .. }
+                | ClientMessage::BrightnessReport { .. }
+                | ClientMessage::Ping { .. }
+                | ClientMessage::Pong { .. } => true,
+            },
+            Self::Empty => true,
+            Self::Batch(payloads) => payloads.iter().all(Self::is_idempotent),
+        }
+    }
+
+    /// Whether the payload can be part of a [`Batch`](FramePayload::Batch). Empty payloads carry nothing worth
+    /// batching, and batches are not nested.
+    pub fn is_batchable(&self) -> bool {
+        !matches!(self, Self::Empty | Self::Batch(_))
+    }
+
+    /// Collect payloads into a `Batch`, see the [`FromIterator`] implementation.
+    pub fn batch(payloads: impl IntoIterator<Item = FramePayload>) -> Self {
+        payloads.into_iter().collect()
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// Collects payloads into a `Batch`, so events accumulated in a loop can be sent at once. Batches are never nested:
+/// batches among the payloads are flattened into their parts, and empty payloads are skipped.
+impl FromIterator<FramePayload> for FramePayload {
+    fn from_iter<I: IntoIterator<Item = FramePayload>>(payloads: I) -> Self {
+        let mut parts = Vec::new();
+        for payload in payloads {
+            match payload {
+                Self::Batch(nested) => parts.extend(nested),
+                Self::Empty => {},
+                payload => parts.push(payload),
+            }
+        }
+
+        Self::Batch(parts)
+    }
+}
+
+/// Messages exchanged with GUI clients. Unknown fields of JSON messages are rejected, so typos don't go unnoticed.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub enum ClientMessage {
     SetBrightness(f32),
     /// Start a game with a specific name.
     StartRound(String),
     /// End the game round if one is currently active.
     EndRound,
+    /// Sent by the master to report that a request from the GUI could not be carried out.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Error {
+        /// What went wrong, as one of the constants in [`error_code`].
+        code: u16,
+        /// A human-readable description of the failure.
+        message: String,
+        /// The message ID of the failed request, if the error can be attributed to one.
+        correlation_id: Option<String>,
+    },
+    /// Sent by a (re)connecting GUI to request the complete current state, answered with a `Snapshot`.
+    RequestSnapshot,
+    /// The complete current state of the master and its mesh, so a reconnecting GUI doesn't depend on incremental updates it missed.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Snapshot {
+        /// The game mode of the current round, `Idle` if no round is active.
+        mode: GameMode,
+        /// The global controller brightness as a percentage between 0.0 and 1.0.
+        brightness: f32,
+        /// Every controller which is currently part of the mesh.
+        nodes: Vec<NodeInfo>,
+    },
+    /// A controller was assigned to a team, see `ControllerMessage::TeamAssign` for the team numbering.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    TeamAssign {
+        controller_id: u16,
+        team: u8,
+        color: (u8, u8, u8),
+    },
+    /// The score of a team changed.
+    TeamScore {
+        team: u8,
+        score: i32,
+    },
+    /// Which controllers answered a `ControllerMessage::ReadyCheck` as ready and which as not ready yet.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    LobbyState {
+        ready: Vec<u16>,
+        not_ready: Vec<u16>,
+    },
+    /// A log line emitted by a controller, forwarded to the GUI for diagnostics.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Log {
+        level: LogLevel,
+        /// The ID of the controller which emitted the line.
+        source_id: u16,
+        message: String,
+        /// The tick at which the line was emitted, to line it up with other events.
+        tick: u16,
+    },
+    /// Sent by the GUI to move a controller into another universe, which the master relays as `ControllerMessage::SetUniverse`.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    SetUniverse {
+        controller_id: u16,
+        universe: u8,
+    },
+    /// The brightness a controller reported in a `ControllerMessage::BrightnessReport`.
+    ///
+    /// The master collects the reports of its controllers and forwards each of them, so the GUI can show the actual
+    /// brightness of every controller instead of assuming the last `SetBrightness` took effect.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    BrightnessReport {
+        controller_id: u16,
+        brightness: f32,
+    },
+    /// A controller sent an emote, see `ControllerMessage::Emote`.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Emote {
+        controller_id: u16,
+        emote: u8,
+    },
+    /// Sent by either side of the WebSocket link to check that the other side is still there, answered with a `Pong`
+    /// carrying the same `nonce`, see [`Pinger`](crate::client::ping::Pinger).
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Ping {
+        nonce: u32,
+    },
+    /// The answer to a `Ping` with the same `nonce`.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    Pong {
+        nonce: u32,
+    },
+    /// Smoothly ramp the global brightness of all controllers to `target` (between 0.0 and 1.0) over `duration_ms`.
+    #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+    FadeBrightness {
+        target: f32,
+        duration_ms: u32,
+    },
+}
+
+/// The severity of a `ClientMessage::Log` line.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// The state of a single controller, as reported to GUI clients in a `Snapshot`.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", deny_unknown_fields))]
+pub struct NodeInfo {
+    /// The assigned ID of the controller.
+    pub id: u16,
+    /// The remaining battery charge as a percentage between 0 and 100.
+    pub battery: u8,
+    /// The color currently shown by the controller, as RGB.
+    pub color: (u8, u8, u8),
+    /// The score of the controller in the current round.
+    pub score: i32,
+}
+
+impl ClientMessage {
+    /// Describe the current state for a reconnecting GUI.
+    pub fn snapshot(mode: GameMode, brightness: f32, nodes: impl IntoIterator<Item = NodeInfo>) -> Self {
+        Self::Snapshot {
+            mode,
+            brightness,
+            nodes: nodes.into_iter().collect(),
+        }
+    }
+
+    /// Report the readiness of the controllers in the lobby.
+    pub fn lobby_state(ready: impl IntoIterator<Item = u16>, not_ready: impl IntoIterator<Item = u16>) -> Self {
+        Self::LobbyState {
+            ready: ready.into_iter().collect(),
+            not_ready: not_ready.into_iter().collect(),
+        }
+    }
+
+    /// Forward a log line emitted by the controller `source_id` at `tick`.
+    pub fn log(level: LogLevel, source_id: u16, message: impl Into<String>, tick: u16) -> Self {
+        Self::Log {
+            level,
+            source_id,
+            message: message.into(),
+            tick,
+        }
+    }
+
+    /// The `Pong` answering this message, if it is a `Ping`.
+    pub fn pong_for(&self) -> Option<Self> {
+        match self {
+            Self::Ping { nonce } => Some(Self::Pong { nonce: *nonce }),
+            _ => None,
+        }
+    }
+
+    /// The game mode after carrying out this message while the game is in `current`, `None` for messages which don't
+    /// start or end a round. Fails if the name of a `StartRound` is no [`GameMode`], or the game can't switch to it,
+    /// see [`GameMode::can_transition_to`].
+    pub fn round_transition(&self, current: &GameMode) -> Result<Option<GameMode>, NackReason> {
+        let next = match self {
+            Self::StartRound(name) => name.parse().map_err(|_e| NackReason::UnknownGameMode(name.clone()))?,
+            Self::EndRound => GameMode::Idle,
+            _ => return Ok(None),
+        };
+
+        if !current.can_transition_to(&next) {
+            return Err(NackReason::IllegalTransition { from: current.clone(), to: next });
+        }
+        Ok(Some(next))
+    }
+
+    /// Report to the GUI that the request with the given message ID was refused.
+    pub fn nack(reason: &NackReason, correlation_id: impl Into<String>) -> Self {
+        Self::Error {
+            code: reason.code(),
+            message: reason.to_string(),
+            correlation_id: Some(correlation_id.into()),
+        }
+    }
+
+    /// Report a failure to the GUI which is not attributed to a specific request.
+    pub fn error(code: u16, message: impl Into<String>) -> Self {
+        Self::Error {
+            code,
+            message: message.into(),
+            correlation_id: None,
+        }
+    }
 }
 
+/// Why the master refused a request of the GUI, reported with [`ClientMessage::nack`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum NackReason {
+    /// A `StartRound` named no known game mode.
+    UnknownGameMode(String),
+    /// The game can't switch from the mode `from` to the mode `to`, like when starting a round while another one is
+    /// still active.
+    IllegalTransition { from: GameMode, to: GameMode },
+}
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+impl NackReason {
+    /// The [`error_code`] reported for this reason.
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::UnknownGameMode(_) => error_code::BAD_REQUEST,
+            Self::IllegalTransition { .. } => error_code::ROUND_STATE_CONFLICT,
+        }
+    }
+}
+
+impl fmt::Display for NackReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownGameMode(name) => write!(f, "unknown game mode {:?}", name),
+            Self::IllegalTransition { from, to } => write!(f, "can't switch from {} to {}", from, to),
+        }
+    }
+}
+
+/// Error codes reported to GUI clients in `ClientMessage::Error`, loosely following HTTP status codes.
+pub mod error_code {
+    /// The request is malformed or not supported by this master.
+    pub const BAD_REQUEST: u16 = 400;
+    /// The request targets a controller which is not part of the mesh.
+    pub const UNKNOWN_CONTROLLER: u16 = 404;
+    /// A round can not be started while another one is still active, or ended while none is.
+    pub const ROUND_STATE_CONFLICT: u16 = 409;
+    /// A value in the request is out of range, like a brightness outside of 0.0 to 1.0.
+    pub const INVALID_VALUE: u16 = 422;
+    /// The master failed to carry out a valid request.
+    pub const INTERNAL: u16 = 500;
+}
+
+/// The emotes of `ControllerMessage::Emote` and `ClientMessage::Emote`. IDs from [`CUSTOM`](emote::CUSTOM) on are
+/// free for emotes defined by the firmware.
+pub mod emote {
+    /// A friendly greeting.
+    pub const WAVE: u8 = 0;
+    /// Provoking the other players.
+    pub const TAUNT: u8 = 1;
+    /// Celebrating, like after winning a round.
+    pub const CHEER: u8 = 2;
+    /// Feeling down, like after being knocked out.
+    pub const SAD: u8 = 3;
+    /// The first ID of firmware-defined emotes.
+    pub const CUSTOM: u8 = 128;
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum InternalMessage {
     /// The current average change of acceleration (jolt) experienced by the controller enclosure, as a vector sum.
     AccelerometerJoltDelta(f32),
     /// A raw accelerometer reading.
     AccelerometerRaw { x: f32, y: f32, z: f32 },
+    /// A raw gyroscope reading of the angular velocity around each axis, on controller revisions equipped with one.
+    GyroscopeRaw { x: f32, y: f32, z: f32 },
+    /// A combined reading of the inertial measurement unit, sampling accelerometer and gyroscope at the same instant.
+    ImuRaw { accel: (f32, f32, f32), gyro: (f32, f32, f32) },
     ClientMessage(ClientMessage),
     Frame(Box<Frame>),
+    /// The local tick counter advanced to the given value, so subsystems like the accelerometer sampler, the scheduler
+    /// and the ack manager can follow the tick on the same event bus as every other internal message.
+    Tick(u16),
+    /// The local node noticed a change of the mesh membership.
+    NetworkEvent(NetworkEvent),
+}
+
+/// A change of the mesh membership observed by the local node, announced on the internal event bus so subsystems like
+/// LED effects and scoring can react to it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NetworkEvent {
+    /// A node joined the mesh with the given ID, emitted by the master when it answers a join request.
+    NodeJoined(u16),
+    /// A node left the mesh on purpose, like when it is redirected to another master.
+    NodeLeft(u16),
+    /// A node stopped sending keep-alive beacons, emitted for every ID returned by [`KeepAlive::remove_dead`].
+    ///
+    /// [`KeepAlive::remove_dead`]: crate::mesh::KeepAlive::remove_dead
+    NodeTimedOut(u16),
+    /// The local node won a master election, see [`Election::current_master`].
+    ///
+    /// [`Election::current_master`]: crate::mesh::Election::current_master
+    BecameMaster,
+    /// The master of the local node disappeared, either because it timed out or because another node won an election.
+    LostMaster,
+}
+
+impl From<NetworkEvent> for InternalMessage {
+    fn from(event: NetworkEvent) -> Self {
+        Self::NetworkEvent(event)
+    }
+}
+
+impl InternalMessage {
+    /// The overall amount of motion described by a raw motion reading, or `None` for other messages.
+    ///
+    /// For accelerometer and gyroscope readings this is the length of the measured vector. For combined IMU readings,
+    /// the lengths of the acceleration and the angular velocity vectors are added up, giving a single activity measure
+    /// which rises with both pushing and twisting the enclosure.
+    pub fn motion_magnitude(&self) -> Option<f32> {
+        match *self {
+            Self::AccelerometerRaw { x, y, z } => Some(magnitude((x, y, z))),
+            Self::GyroscopeRaw { x, y, z } => Some(magnitude((x, y, z))),
+            Self::ImuRaw { accel, gyro } => Some(magnitude(accel) + magnitude(gyro)),
+            _ => None,
+        }
+    }
+}
+
+fn magnitude((x, y, z): (f32, f32, f32)) -> f32 {
+    (x * x + y * y + z * z).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn illegal_round_transitions_are_refused() {
+        let start = ClientMessage::StartRound("territory".to_string());
+        assert_eq!(start.round_transition(&GameMode::Idle), Ok(Some(GameMode::Territory)));
+        assert_eq!(ClientMessage::EndRound.round_transition(&GameMode::Territory), Ok(Some(GameMode::Idle)));
+        assert_eq!(ClientMessage::RequestSnapshot.round_transition(&GameMode::Territory), Ok(None));
+
+        let refused = start.round_transition(&GameMode::LastOneStanding).unwrap_err();
+        assert_eq!(refused, NackReason::IllegalTransition { from: GameMode::LastOneStanding, to: GameMode::Territory });
+        assert_eq!(ClientMessage::nack(&refused, "V1StGXR8_Z"), ClientMessage::Error {
+            code: error_code::ROUND_STATE_CONFLICT,
+            message: "can't switch from last-one-standing to territory".to_string(),
+            correlation_id: Some("V1StGXR8_Z".to_string()),
+        });
+
+        let unknown = ClientMessage::StartRound("tag".to_string()).round_transition(&GameMode::Idle).unwrap_err();
+        assert_eq!(unknown, NackReason::UnknownGameMode("tag".to_string()));
+        assert_eq!(unknown.code(), error_code::BAD_REQUEST);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn fade_brightness_json_round_trip() {
+        let msg = ClientMessage::FadeBrightness { target: 0.5, duration_ms: 250 };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(json, r#"{"FadeBrightness":{"target":0.5,"durationMs":250}}"#);
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn snapshot_json_round_trip() {
+        let msg = ClientMessage::snapshot(GameMode::Territory, 0.8, [
+            NodeInfo { id: 1, battery: 90, color: (255, 0, 0), score: 3 },
+            NodeInfo { id: 2, battery: 15, color: (0, 0, 255), score: -1 },
+        ]);
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+
+        let request = serde_json::to_string(&ClientMessage::RequestSnapshot).unwrap();
+        assert_eq!(request, r#""RequestSnapshot""#);
+        assert_eq!(ClientMessage::RequestSnapshot, serde_json::from_str::<ClientMessage>(&request).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn client_error_json_round_trip() {
+        let msg = ClientMessage::Error {
+            code: error_code::ROUND_STATE_CONFLICT,
+            message: "a round is already active".to_string(),
+            correlation_id: Some("V1StGXR8_Z".to_string()),
+        };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"correlationId\":\"V1StGXR8_Z\""));
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+
+        let msg = ClientMessage::error(error_code::INVALID_VALUE, "brightness out of range");
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn team_json_round_trip() {
+        let msg = ClientMessage::TeamAssign { controller_id: 3, team: 1, color: (0, 255, 0) };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(json, r#"{"TeamAssign":{"controllerId":3,"team":1,"color":[0,255,0]}}"#);
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+
+        let msg = ClientMessage::TeamScore { team: 1, score: -4 };
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn lobby_state_json_round_trip() {
+        let msg = ClientMessage::lobby_state([1, 3], [2]);
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(json, r#"{"LobbyState":{"ready":[1,3],"notReady":[2]}}"#);
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn log_json_round_trip() {
+        let msg = ClientMessage::log(LogLevel::Warn, 4, "battery low", 1200);
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(json, r#"{"Log":{"level":"warn","sourceId":4,"message":"battery low","tick":1200}}"#);
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+    }
+
+    #[test]
+    fn imu_motion_magnitude() {
+        let gyro = InternalMessage::GyroscopeRaw { x: 0.0, y: 3.0, z: 4.0 };
+        assert_eq!(gyro.motion_magnitude(), Some(5.0));
+
+        let imu = InternalMessage::ImuRaw { accel: (0.0, 0.0, 1.0), gyro: (3.0, 0.0, 4.0) };
+        assert_eq!(imu.motion_magnitude(), Some(6.0));
+
+        assert_eq!(InternalMessage::AccelerometerJoltDelta(1.0).motion_magnitude(), None);
+    }
+
+    #[test]
+    fn internal_tick() {
+        let msg = InternalMessage::Tick(42);
+
+        assert!(matches!(msg, InternalMessage::Tick(42)));
+        assert_eq!(msg.motion_magnitude(), None);
+    }
+
+    #[test]
+    fn network_events() {
+        let msg = InternalMessage::from(NetworkEvent::NodeJoined(3));
+
+        assert_eq!(msg, InternalMessage::NetworkEvent(NetworkEvent::NodeJoined(3)));
+        assert_ne!(NetworkEvent::NodeLeft(3), NetworkEvent::NodeTimedOut(3));
+        assert_eq!(msg.motion_magnitude(), None);
+        assert!(matches!(InternalMessage::from(NetworkEvent::LostMaster), InternalMessage::NetworkEvent(NetworkEvent::LostMaster)));
+    }
+
+    #[test]
+    fn collect_into_flat_batch() {
+        let score = |team| FramePayload::ControllerMessage(ControllerMessage::TeamScore { team, score: 1 });
+
+        let batch: FramePayload = [score(0), FramePayload::batch([score(1), FramePayload::Empty]), score(2)].into_iter().collect();
+        assert_eq!(batch, FramePayload::Batch(vec![score(0), score(1), score(2)]));
+
+        let FramePayload::Batch(parts) = batch else { unreachable!() };
+        assert!(parts.iter().all(FramePayload::is_batchable));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn emote_json_round_trip() {
+        let msg = ClientMessage::Emote { controller_id: 4, emote: emote::TAUNT };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(json, r#"{"Emote":{"controllerId":4,"emote":1}}"#);
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn set_universe_json_round_trip() {
+        let msg = ClientMessage::SetUniverse { controller_id: 5, universe: 2 };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(json, r#"{"SetUniverse":{"controllerId":5,"universe":2}}"#);
+        assert_eq!(msg, serde_json::from_str::<ClientMessage>(&json).unwrap());
+    }
 }
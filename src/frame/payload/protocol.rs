@@ -1,10 +1,84 @@
+#[cfg(feature = "serde")]
 use serde_derive::{Serialize, Deserialize};
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ProtocolMessage {
     Acknowledged {
         /// The unique identifier of the message being acknowledged.
         message_id: String,
     },
     Tick(u16),
-}
\ No newline at end of file
+    /// Beacon announcing that the sender is still alive, and that it will send the next beacon within `interval_ticks` ticks.
+    KeepAlive {
+        interval_ticks: u16,
+    },
+    /// Sent to start a master election or to take part in one, proposing `candidate_id` as the new master.
+    Election {
+        candidate_id: u16,
+    },
+    /// Announces the winner of a master election to all nodes.
+    Coordinator {
+        master_id: u16,
+    },
+    /// Reports how far the sender's tick was off from the master's, with a positive `drift` if the sender is ahead.
+    ClockDrift {
+        observed_tick: u16,
+        expected_tick: u16,
+        drift:         i32,
+    },
+    /// Announces the protocol version of the sender and the optional features it supports, as a bitfield of
+    /// [`Capabilities`](crate::mesh::caps::Capabilities).
+    Hello {
+        version:      u8,
+        capabilities: u32,
+    },
+    /// The bincode encoding of the actual payload, compressed with the algorithm `algo`, see
+    /// [`compression`](crate::frame::compression) with the `compression` feature.
+    Compressed {
+        algo: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::frame::bytes"))]
+        data: Vec<u8>,
+    },
+    /// Acknowledges several messages at once, at most [`MAX_ACK_BATCH`](crate::mesh::ack::MAX_ACK_BATCH) of them.
+    AcknowledgedBatch {
+        message_ids: Vec<String>,
+    },
+    /// The actual payload, encrypted for the target of the frame with the key `key_id`. The header stays in the clear,
+    /// so relays forward the frame without being able to read it.
+    Encrypted {
+        key_id: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::frame::bytes"))]
+        data:   Vec<u8>,
+    },
+    /// Cancels the multi-frame transaction started by the frame with `message_id`, so the receiver drops all state it
+    /// keeps for it, like a pending retransmission.
+    Abort {
+        message_id: String,
+        reason:     AbortReason,
+    },
+}
+
+/// Why a transaction was cancelled with `ProtocolMessage::Abort`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AbortReason {
+    /// The other side took too long to answer.
+    Timeout,
+    /// The sender no longer needs the transaction, for example because its target left the mesh.
+    Cancelled,
+    /// The sender ran out of memory or another resource for carrying out the transaction.
+    Resource,
+}
+
+impl ProtocolMessage {
+    /// A drift report for a node which was at `observed_tick` when the master was at `expected_tick`, which stays
+    /// correct when only one of the two counters has wrapped around.
+    pub fn clock_drift(observed_tick: u16, expected_tick: u16) -> Self {
+        Self::ClockDrift {
+            observed_tick,
+            expected_tick,
+            drift: crate::mesh::tick::offset(expected_tick, observed_tick) as i32,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! A pool of byte buffers recycled across encoded frames, so a node sending on every tick doesn't allocate a fresh
+//! buffer for every frame.
+//!
+//! The pool is a plain value without any locking, meant to be owned by the single loop of the firmware which sends
+//! frames. Threads sending concurrently should each own a pool of their own.
+
+use crate::frame::{Frame, FrameError};
+
+/// The number of buffers a pool keeps by default, further returned buffers are dropped.
+pub const DEFAULT_POOL_CAPACITY: usize = 16;
+
+/// Recycles buffers handed back with [`BufferPool::put`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BufferPool {
+    buffers:  Vec<Vec<u8>>,
+    capacity: usize,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_POOL_CAPACITY)
+    }
+
+    /// A pool keeping at most `capacity` unused buffers.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { buffers: Vec::with_capacity(capacity), capacity }
+    }
+
+    /// Take an empty buffer from the pool, allocating a new one if none is left.
+    pub fn get(&mut self) -> Vec<u8> {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Hand a buffer back to the pool once it is no longer needed, like after it was transmitted. The buffer is
+    /// cleared, keeping its allocation for the next [`BufferPool::get`].
+    pub fn put(&mut self, mut buffer: Vec<u8>) {
+        if self.buffers.len() < self.capacity {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+
+    /// The number of unused buffers in the pool.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Frame {
+    /// Encode the frame for the wire into a buffer taken from the pool, which should be put back after sending it.
+    ///
+    /// If encoding fails, the buffer is returned to the pool right away.
+    pub fn serialize_pooled(&self, pool: &mut BufferPool) -> Result<Vec<u8>, FrameError> {
+        let mut buffer = pool.get();
+        match self.write_to(&mut buffer) {
+            Ok(()) => Ok(buffer),
+            Err(e) => {
+                pool.put(buffer);
+                Err(e)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::MAX_FRAME_SIZE;
+
+    #[test]
+    fn pooled_buffers_are_reused() {
+        let mut pool = BufferPool::new();
+        // Large enough for any frame, so no encoding has to grow the buffer.
+        pool.put(Vec::with_capacity(2 * MAX_FRAME_SIZE));
+
+        let mut first_allocation = None;
+        for tick in 0 .. 100 {
+            let frame = Frame::tick(tick);
+            let bytes = frame.serialize_pooled(&mut pool).unwrap();
+            assert_eq!(bytes, frame.to_bytes().unwrap());
+
+            let allocation = (bytes.as_ptr(), bytes.capacity());
+            assert_eq!(*first_allocation.get_or_insert(allocation), allocation);
+            pool.put(bytes);
+        }
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn pool_is_bounded() {
+        let mut pool = BufferPool::with_capacity(2);
+        for _ in 0 .. 5 {
+            pool.put(vec![1, 2, 3]);
+        }
+
+        assert_eq!(pool.len(), 2);
+        assert!(pool.get().is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+//! Conversions for the four ranging bytes trailing every frame, which the UWB module writes on transmission, and the
+//! distance calculation of explicit two-way ranging exchanges.
+//!
+//! On the wire the bytes follow the bincode body as they are, so a `u32` is stored little-endian like every other
+//! integer of the encoding.
+
+use crate::frame::{Frame, FrameHeader};
+
+/// The ranging data of a frame, convertible from the common representations firmware reads it in.
+///
+/// Register types of a particular UWB driver can implement `Into<RangingData>` to be passed to [`Frame::ranging`]
+/// directly.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct RangingData(pub [u8; 4]);
+
+impl RangingData {
+    /// Ranging data holding the value in little-endian byte order.
+    pub fn from_u32(value: u32) -> Self {
+        Self(value.to_le_bytes())
+    }
+
+    /// The bytes interpreted as a little-endian `u32`.
+    pub fn to_u32(&self) -> u32 {
+        u32::from_le_bytes(self.0)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+}
+
+impl From<u32> for RangingData {
+    fn from(value: u32) -> Self {
+        Self::from_u32(value)
+    }
+}
+
+impl From<[u8; 4]> for RangingData {
+    fn from(bytes: [u8; 4]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<RangingData> for [u8; 4] {
+    fn from(ranging: RangingData) -> Self {
+        ranging.0
+    }
+}
+
+impl From<RangingData> for u32 {
+    fn from(ranging: RangingData) -> Self {
+        ranging.to_u32()
+    }
+}
+
+/// The duration of a single tick of the DW1000 system clock in seconds, the unit of all UWB timestamps.
+pub const DW1000_TIME_UNIT: f64 = 1.0 / (128.0 * 499.2e6);
+
+/// DW1000 timestamps are 40 bits wide and wrap around after about 17 seconds.
+pub const DW1000_TIMESTAMP_MASK: u64 = (1 << 40) - 1;
+
+/// The speed of radio waves in air in meters per second.
+pub const SPEED_OF_LIGHT_AIR: f64 = 299_702_547.0;
+
+/// A single-sided two-way ranging exchange, measuring the distance to a peer by the time of flight of a
+/// `RangingRequest` and the peer's `RangingResponse`.
+///
+/// ```text
+/// initiator  poll_sent_at ─────────────────────────────── response_received_at
+///                  └── RangingRequest ──┐        ┌── RangingResponse ──┘
+/// responder                             └ t_reply ┘
+/// ```
+///
+/// All timestamps are in DW1000 time units. The initiator's and the responder's clocks don't have to be synchronized,
+/// since each only measures a duration on its own clock. Clock drift between the two adds an error proportional to
+/// `t_reply`, so the responder should answer as quickly as possible.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TwoWayRanging {
+    /// When the initiator sent the `RangingRequest`, on its own clock.
+    pub poll_sent_at:         u64,
+    /// When the initiator received the `RangingResponse`, on its own clock.
+    pub response_received_at: u64,
+    /// The `t_reply` of the `RangingResponse`.
+    pub t_reply:              u64,
+}
+
+impl TwoWayRanging {
+    pub fn new(poll_sent_at: u64, response_received_at: u64, t_reply: u64) -> Self {
+        Self { poll_sent_at, response_received_at, t_reply }
+    }
+
+    /// The one-way time of flight in DW1000 time units, handling a wrap of the 40-bit timestamps in between. A reply
+    /// delay longer than the round trip, which can only stem from a bogus measurement, results in zero.
+    pub fn time_of_flight(&self) -> u64 {
+        let round_trip = self.response_received_at.wrapping_sub(self.poll_sent_at) & DW1000_TIMESTAMP_MASK;
+        round_trip.saturating_sub(self.t_reply) / 2
+    }
+
+    /// The distance to the peer in meters.
+    pub fn distance_m(&self) -> f64 {
+        self.time_of_flight() as f64 * DW1000_TIME_UNIT * SPEED_OF_LIGHT_AIR
+    }
+}
+
+impl FrameHeader {
+    /// The ranging bytes as a [`RangingData`].
+    pub fn ranging(&self) -> RangingData {
+        RangingData(self.ranging_bytes)
+    }
+
+    /// The ranging bytes interpreted as a little-endian `u32`.
+    pub fn ranging_as_u32(&self) -> u32 {
+        self.ranging().to_u32()
+    }
+
+    pub fn set_ranging(&mut self, ranging: impl Into<RangingData>) {
+        self.ranging_bytes = ranging.into().0;
+    }
+}
+
+impl Frame {
+    /// Set the ranging bytes from a `u32`, a `[u8; 4]` or anything else convertible into [`RangingData`].
+    pub fn ranging(mut self, ranging: impl Into<RangingData>) -> Self {
+        self.header.set_ranging(ranging);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u32_and_bytes_are_little_endian() {
+        assert_eq!(RangingData::from_u32(0x0403_0201).0, [1, 2, 3, 4]);
+        assert_eq!(RangingData::from([1, 2, 3, 4]).to_u32(), 0x0403_0201);
+        assert_eq!(u32::from(RangingData::from(u32::MAX - 7)), u32::MAX - 7);
+        assert_eq!(<[u8; 4]>::from(RangingData::from([9, 8, 7, 6])), [9, 8, 7, 6]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ranging_survives_the_wire() {
+        let frame = Frame::tick(3).ranging(0xDEAD_BEEF_u32);
+        assert_eq!(frame.header.ranging_as_u32(), 0xDEAD_BEEF);
+
+        let bytes = frame.to_bytes().unwrap();
+        assert_eq!(bytes[bytes.len() - 4 ..], [0xEF, 0xBE, 0xAD, 0xDE]);
+        assert_eq!(Frame::try_from(&bytes[..]).unwrap().header.ranging(), RangingData::from_u32(0xDEAD_BEEF));
+    }
+
+    #[test]
+    fn two_way_ranging_distance() {
+        // 10 meters take about 33.4 ns, or 2133 time units, each way.
+        let ranging = TwoWayRanging::new(1_000, 1_000 + 2 * 2133 + 50_000, 50_000);
+        assert_eq!(ranging.time_of_flight(), 2133);
+        assert!((ranging.distance_m() - 10.0).abs() < 0.01);
+
+        let wrapped = TwoWayRanging::new(DW1000_TIMESTAMP_MASK - 99, 2 * 2133 + 50_000 - 100, 50_000);
+        assert_eq!(wrapped.time_of_flight(), 2133);
+        assert_eq!(TwoWayRanging::new(0, 100, 200).distance_m(), 0.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ranging_exchange_round_trip() {
+        let request = Frame::ranging_request(4).sender_id(2);
+        let response = Frame::ranging_response(2, 50_000).sender_id(4);
+
+        assert_eq!(Frame::try_from(&request.to_bytes().unwrap()[..]), Ok(request));
+        assert_eq!(Frame::try_from(&response.to_bytes().unwrap()[..]), Ok(response));
+    }
+}
@@ -0,0 +1,196 @@
+//! The framing of encoded frames without decoding their bodies, for relays which forward frames as opaque bytes.
+//!
+//! This is available without the `serde` feature: a [`RawFrame`] is split off and assembled by hand, following the
+//! layout described in [`crate::frame`]. For bodies in the standard encoding, the header can also be told apart from
+//! the payload and checked against its [checksum](crate::frame::checksum), so a relay can drop frames corrupted in
+//! transit without decoding them.
+
+use crate::frame::checksum::{crc16, HEADER_CHECKSUM_LEN};
+use crate::frame::{frame_len, framed_body, FrameError, ENCODING_BINCODE, MAGIC, MAX_FRAME_SIZE, PREFIX_LEN};
+use crate::wire::RANGING_LEN;
+
+/// An encoded frame split into its format tag, its still encoded body and its ranging bytes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawFrame<'a> {
+    /// The format tag, like [`ENCODING_BINCODE`](crate::frame::ENCODING_BINCODE).
+    pub encoding:      u8,
+    pub body:          &'a [u8],
+    pub ranging_bytes: [u8; RANGING_LEN],
+}
+
+impl<'a> RawFrame<'a> {
+    pub fn new(encoding: u8, body: &'a [u8], ranging_bytes: [u8; RANGING_LEN]) -> Self {
+        Self { encoding, body, ranging_bytes }
+    }
+
+    /// The length of the frame at the start of the buffer as announced by its prefix, which may be more than the
+    /// buffer holds so far, for splitting a stream of bytes into frames.
+    pub fn peek_len(bytes: &[u8]) -> Result<usize, FrameError> {
+        frame_len(bytes)
+    }
+
+    /// Split the frame at the start of the buffer into its parts, returning it along with the number of bytes it took
+    /// up. Fails like `Frame::try_from_prefix` on anything but the body.
+    pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), FrameError> {
+        let framed = framed_body(bytes)?;
+        Ok((Self::new(framed.encoding, framed.body, framed.ranging_bytes), framed.len))
+    }
+
+    /// The number of bytes the frame takes up on the wire.
+    pub fn encoded_len(&self) -> usize {
+        PREFIX_LEN + self.body.len() + RANGING_LEN
+    }
+
+    /// Append the encoded frame to `buffer`, failing with `FrameError::SerializeError` if the body is longer than
+    /// [`MAX_FRAME_SIZE`].
+    pub fn write_to(&self, buffer: &mut Vec<u8>) -> Result<(), FrameError> {
+        if self.body.len() > MAX_FRAME_SIZE {
+            return Err(FrameError::SerializeError);
+        }
+
+        buffer.reserve(self.encoded_len());
+        buffer.extend_from_slice(MAGIC);
+        buffer.push(self.encoding);
+        buffer.extend_from_slice(&(self.body.len() as u16).to_le_bytes());
+        buffer.extend_from_slice(self.body);
+        buffer.extend_from_slice(&self.ranging_bytes);
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FrameError> {
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        self.write_to(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    /// The encoded header and the encoded payload of a body in the standard encoding, leaving out the header checksum
+    /// between them. `None` for other encodings and for bodies whose header is malformed.
+    pub fn split_header(&self) -> Option<(&'a [u8], &'a [u8])> {
+        let header_len = self.header_len()?;
+        let payload = self.body.get(header_len + HEADER_CHECKSUM_LEN ..)?;
+
+        Some((&self.body[.. header_len], payload))
+    }
+
+    /// Whether the body is in the standard encoding and its header matches its checksum.
+    pub fn verify_header_checksum(&self) -> bool {
+        self.split_header().is_some_and(|(header, _)| {
+            let checksum = &self.body[header.len() .. header.len() + HEADER_CHECKSUM_LEN];
+            u16::from_le_bytes([checksum[0], checksum[1]]) == crc16(header)
+        })
+    }
+
+    /// Walk the fields of a `FrameHeader` in the standard encoding to find where it ends. The fields have to be listed
+    /// here in the same order as in the struct.
+    fn header_len(&self) -> Option<usize> {
+        if self.encoding != ENCODING_BINCODE {
+            return None;
+        }
+
+        let mut reader = Reader { bytes: self.body, at: 0 };
+        reader.string()?; // timestamp
+        reader.skip(1)?; // lifetime
+        reader.string()?; // message_id
+        reader.skip(2)?; // sender_id
+        reader.option(|reader| reader.skip(2))?; // target_id
+        reader.skip(1)?; // requires_acknowledgement
+        reader.option(Reader::string)?; // acked_message_id
+        reader.skip(2)?; // current_tick
+        reader.skip(1)?; // universe
+        reader.skip(4)?; // ranging_bytes
+        reader.option(|reader| reader.skip(2 + 64))?; // signature
+        let visited = reader.length()?;
+        reader.skip(visited.checked_mul(2)?)?; // visited
+        reader.option(|reader| reader.skip(2))?; // ack_timeout_ticks
+        reader.option(|reader| reader.skip(2))?; // deadline_tick
+
+        Some(reader.at)
+    }
+}
+
+/// Skips over fields encoded with the fixed-width bincode configuration of [`crate::wire`].
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at:    usize,
+}
+
+impl Reader<'_> {
+    fn skip(&mut self, len: usize) -> Option<()> {
+        let end = self.at.checked_add(len)?;
+        (end <= self.bytes.len()).then(|| self.at = end)
+    }
+
+    fn length(&mut self) -> Option<usize> {
+        let bytes = self.bytes.get(self.at .. self.at + 8)?;
+        self.at += 8;
+        usize::try_from(u64::from_le_bytes(bytes.try_into().ok()?)).ok()
+    }
+
+    fn string(&mut self) -> Option<()> {
+        let len = self.length()?;
+        self.skip(len)
+    }
+
+    fn option(&mut self, some: impl FnOnce(&mut Self) -> Option<()>) -> Option<()> {
+        let tag = *self.bytes.get(self.at)?;
+        self.at += 1;
+        match tag {
+            0 => Some(()),
+            1 => some(self),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::ENCODING_COMPACT;
+
+    #[test]
+    fn raw_round_trip() {
+        let raw = RawFrame::new(ENCODING_COMPACT, b"opaque body", [1, 2, 3, 4]);
+        let mut bytes = raw.to_bytes().unwrap();
+        assert_eq!(bytes.len(), raw.encoded_len());
+        assert_eq!(&bytes[.. MAGIC.len()], MAGIC);
+
+        bytes.extend_from_slice(b"next frame");
+        assert_eq!(RawFrame::peek_len(&bytes), Ok(raw.encoded_len()));
+        assert_eq!(RawFrame::parse(&bytes), Ok((raw, raw.encoded_len())));
+        assert_eq!(raw.split_header(), None);
+        assert!(!raw.verify_header_checksum());
+
+        assert_eq!(RawFrame::parse(&bytes[.. 20]), Err(FrameError::TooShort { expected: raw.encoded_len(), actual: 20 }));
+        assert_eq!(RawFrame::new(ENCODING_COMPACT, &[0; MAX_FRAME_SIZE + 1], [0; 4]).to_bytes(), Err(FrameError::SerializeError));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn header_checksum_matches_decoding() {
+        use bincode::Options;
+
+        use crate::test_util::arbitrary_frame;
+        use crate::frame::Frame;
+        use crate::wire;
+
+        for seed in 0 .. 200 {
+            let frame = arbitrary_frame(seed);
+            let mut bytes = frame.to_bytes().unwrap();
+            let (raw, len) = RawFrame::parse(&bytes).unwrap();
+            assert_eq!(len, bytes.len());
+            assert_eq!(raw.to_bytes().unwrap(), bytes);
+
+            let (header, payload) = raw.split_header().unwrap();
+            assert_eq!(header, wire::bincode_options().serialize(&frame.header).unwrap(), "seed {}", seed);
+            assert_eq!(payload, wire::bincode_options().serialize(&frame.payload).unwrap(), "seed {}", seed);
+            assert!(raw.verify_header_checksum());
+
+            // The low byte of the sender ID, as in the checksum tests.
+            bytes[PREFIX_LEN + 8 + frame.header.timestamp.len() + 1 + 8 + frame.header.message_id.len()] ^= 0x01;
+            assert!(!RawFrame::parse(&bytes).unwrap().0.verify_header_checksum());
+            assert!(!Frame::verify_header_checksum(&bytes));
+        }
+    }
+}
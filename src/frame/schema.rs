@@ -0,0 +1,434 @@
+//! Introspection of the structure of a frame payload, for tooling like generic inspectors or schema exporters.
+//!
+//! Instead of keeping a separate description of every message in sync with the code, the schema is read from the
+//! payload itself by serializing it into a [`serde::Serializer`] which only records the names of variants, fields and
+//! types. Field names are reported as they are serialized, so renamed fields appear in camelCase just like in JSON.
+
+use core::fmt;
+
+use serde::ser::{self, Impossible, Serialize, Serializer};
+
+use crate::frame::{Frame, FramePayload};
+
+/// The structure of the payload of a frame.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PayloadSchema {
+    /// The kind of payload, like `ControllerMessage` or `ClientMessage`, `Empty` for frames without a payload or `Batch`.
+    pub kind: &'static str,
+    /// The name of the message variant, like `JoinResponse`, or `Empty` for frames without a payload.
+    pub variant: &'static str,
+    /// The name and type of every field of the variant, in declaration order. Fields of tuple variants are named by
+    /// their position, starting at `"0"`.
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl Frame {
+    /// Describe the variant and fields of the payload of this frame.
+    ///
+    /// Types are named after the Rust types they are declared with, as far as the serialized value reveals them, so
+    /// an `Option` which is `None` or an empty `Vec` is described without its inner type.
+    pub fn payload_schema(&self) -> PayloadSchema {
+        let (kind, variant) = match &self.payload {
+            FramePayload::ControllerMessage(msg) => ("ControllerMessage", msg.serialize(VariantSerializer)),
+            FramePayload::ProtocolMessage(msg) => ("ProtocolMessage", msg.serialize(VariantSerializer)),
+            FramePayload::ClientMessage(msg) => ("ClientMessage", msg.serialize(VariantSerializer)),
+            FramePayload::Empty => ("Empty", Ok(("Empty", Vec::new()))),
+            FramePayload::Batch(_) => ("Batch", Ok(("Batch", vec![("0", "Vec<FramePayload>".to_string())]))),
+        };
+        let (variant, fields) = variant.expect("all messages are enums");
+
+        PayloadSchema { kind, variant, fields }
+    }
+}
+
+/// Raised for values which can not appear at the position they were serialized at, like a message which is not an enum.
+#[derive(Debug)]
+struct Unsupported(String);
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
+impl ser::Error for Unsupported {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// Names of the fields of tuple variants.
+const POSITIONS: [&str; 8] = ["0", "1", "2", "3", "4", "5", "6", "7"];
+
+type Variant = (&'static str, Vec<(&'static str, String)>);
+
+/// Records the name and fields of a message enum variant.
+struct VariantSerializer;
+
+macro_rules! unsupported {
+    ($($method:ident($($arg:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Self::Ok, Self::Error> {
+                Err(Unsupported(concat!("messages must be enum variants, found ", stringify!($method)).to_string()))
+            }
+        )*
+    };
+}
+
+impl Serializer for VariantSerializer {
+    type Ok = Variant;
+    type Error = Unsupported;
+    type SerializeSeq = Impossible<Variant, Unsupported>;
+    type SerializeTuple = Impossible<Variant, Unsupported>;
+    type SerializeTupleStruct = Impossible<Variant, Unsupported>;
+    type SerializeTupleVariant = FieldRecorder;
+    type SerializeMap = Impossible<Variant, Unsupported>;
+    type SerializeStruct = Impossible<Variant, Unsupported>;
+    type SerializeStructVariant = FieldRecorder;
+
+    unsupported! {
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64),
+        serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64), serialize_f32(f32),
+        serialize_f64(f64), serialize_char(char), serialize_str(&str), serialize_bytes(&[u8]), serialize_none(),
+        serialize_unit(), serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Variant, Unsupported> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Variant, Unsupported> {
+        Ok((variant, Vec::new()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<Variant, Unsupported> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<Variant, Unsupported> {
+        Ok((variant, vec![(POSITIONS[0], value.serialize(TypeNamer)?)]))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Unsupported> {
+        Err(Unsupported("messages must be enum variants, found a sequence".to_string()))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Unsupported> {
+        Err(Unsupported("messages must be enum variants, found a tuple".to_string()))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Unsupported> {
+        Err(Unsupported(format!("messages must be enum variants, found {}", name)))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<FieldRecorder, Unsupported> {
+        Ok(FieldRecorder { variant, fields: Vec::new() })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Unsupported> {
+        Err(Unsupported("messages must be enum variants, found a map".to_string()))
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Unsupported> {
+        Err(Unsupported(format!("messages must be enum variants, found {}", name)))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<FieldRecorder, Unsupported> {
+        Ok(FieldRecorder { variant, fields: Vec::new() })
+    }
+}
+
+/// Collects the fields of a tuple or struct variant.
+struct FieldRecorder {
+    variant: &'static str,
+    fields:  Vec<(&'static str, String)>,
+}
+
+impl ser::SerializeTupleVariant for FieldRecorder {
+    type Ok = Variant;
+    type Error = Unsupported;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Unsupported> {
+        let position = POSITIONS.get(self.fields.len()).ok_or_else(|| Unsupported("too many tuple fields".to_string()))?;
+        self.fields.push((position, value.serialize(TypeNamer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Variant, Unsupported> {
+        Ok((self.variant, self.fields))
+    }
+}
+
+impl ser::SerializeStructVariant for FieldRecorder {
+    type Ok = Variant;
+    type Error = Unsupported;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Unsupported> {
+        self.fields.push((key, value.serialize(TypeNamer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Variant, Unsupported> {
+        Ok((self.variant, self.fields))
+    }
+}
+
+/// Names the type of a serialized value.
+struct TypeNamer;
+
+macro_rules! primitive {
+    ($($method:ident($arg:ty) => $name:literal),* $(,)?) => {
+        $(
+            fn $method(self, _: $arg) -> Result<String, Unsupported> {
+                Ok($name.to_string())
+            }
+        )*
+    };
+}
+
+impl Serializer for TypeNamer {
+    type Ok = String;
+    type Error = Unsupported;
+    type SerializeSeq = ElementNamer;
+    type SerializeTuple = ElementNamer;
+    type SerializeTupleStruct = Named;
+    type SerializeTupleVariant = Named;
+    type SerializeMap = Named;
+    type SerializeStruct = Named;
+    type SerializeStructVariant = Named;
+
+    primitive! {
+        serialize_bool(bool) => "bool", serialize_i8(i8) => "i8", serialize_i16(i16) => "i16",
+        serialize_i32(i32) => "i32", serialize_i64(i64) => "i64", serialize_u8(u8) => "u8",
+        serialize_u16(u16) => "u16", serialize_u32(u32) => "u32", serialize_u64(u64) => "u64",
+        serialize_f32(f32) => "f32", serialize_f64(f64) => "f64", serialize_char(char) => "char",
+        serialize_str(&str) => "String", serialize_bytes(&[u8]) => "Vec<u8>",
+    }
+
+    // Describe the types of the binary encoding, not the base64 strings of `frame::bytes` fields in JSON.
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_none(self) -> Result<String, Unsupported> {
+        Ok("Option".to_string())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<String, Unsupported> {
+        Ok(format!("Option<{}>", value.serialize(self)?))
+    }
+
+    fn serialize_unit(self) -> Result<String, Unsupported> {
+        Ok("()".to_string())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<String, Unsupported> {
+        Ok(name.to_string())
+    }
+
+    fn serialize_unit_variant(self, name: &'static str, _index: u32, _variant: &'static str) -> Result<String, Unsupported> {
+        Ok(name.to_string())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, name: &'static str, _value: &T) -> Result<String, Unsupported> {
+        Ok(name.to_string())
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<String, Unsupported> {
+        Ok(name.to_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<ElementNamer, Unsupported> {
+        Ok(ElementNamer { tuple: false, elements: Vec::new() })
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<ElementNamer, Unsupported> {
+        Ok(ElementNamer { tuple: true, elements: Vec::new() })
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, _len: usize) -> Result<Named, Unsupported> {
+        Ok(Named(name.to_string()))
+    }
+
+    fn serialize_tuple_variant(self, name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Named, Unsupported> {
+        Ok(Named(name.to_string()))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Named, Unsupported> {
+        Ok(Named("Map".to_string()))
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Named, Unsupported> {
+        Ok(Named(name.to_string()))
+    }
+
+    fn serialize_struct_variant(self, name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Named, Unsupported> {
+        Ok(Named(name.to_string()))
+    }
+}
+
+/// Names sequences after their first element and tuples after all of their elements.
+struct ElementNamer {
+    tuple:    bool,
+    elements: Vec<String>,
+}
+
+impl ElementNamer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Unsupported> {
+        if self.tuple || self.elements.is_empty() {
+            self.elements.push(value.serialize(TypeNamer)?);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> String {
+        match (self.tuple, self.elements.first()) {
+            (true, _) => format!("({})", self.elements.join(", ")),
+            (false, Some(element)) => format!("Vec<{}>", element),
+            (false, None) => "Vec".to_string(),
+        }
+    }
+}
+
+impl ser::SerializeSeq for ElementNamer {
+    type Ok = String;
+    type Error = Unsupported;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Unsupported> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<String, Unsupported> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for ElementNamer {
+    type Ok = String;
+    type Error = Unsupported;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Unsupported> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<String, Unsupported> {
+        Ok(self.finish())
+    }
+}
+
+/// A compound value which is named after its type, ignoring its contents.
+struct Named(String);
+
+impl ser::SerializeTupleStruct for Named {
+    type Ok = String;
+    type Error = Unsupported;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Unsupported> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<String, Unsupported> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeTupleVariant for Named {
+    type Ok = String;
+    type Error = Unsupported;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Unsupported> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<String, Unsupported> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeMap for Named {
+    type Ok = String;
+    type Error = Unsupported;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, _key: &T) -> Result<(), Unsupported> {
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Unsupported> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<String, Unsupported> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeStruct for Named {
+    type Ok = String;
+    type Error = Unsupported;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, _value: &T) -> Result<(), Unsupported> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<String, Unsupported> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeStructVariant for Named {
+    type Ok = String;
+    type Error = Unsupported;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, _value: &T) -> Result<(), Unsupported> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<String, Unsupported> {
+        Ok(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{ClientMessage, ControllerMessage, NodeInfo, ProtocolMessage};
+    use crate::packet::GameMode;
+
+    fn schema(frame: Frame) -> (&'static str, &'static str, Vec<(&'static str, String)>) {
+        let schema = frame.payload_schema();
+        (schema.kind, schema.variant, schema.fields)
+    }
+
+    #[test]
+    fn set_brightness_schema() {
+        let frame = Frame::new().client_message(ClientMessage::SetBrightness(0.5));
+        assert_eq!(schema(frame), ("ClientMessage", "SetBrightness", vec![("0", "f32".to_string())]));
+    }
+
+    #[test]
+    fn join_response_schema() {
+        assert_eq!(schema(Frame::join_response(0, 4)), ("ControllerMessage", "JoinResponse", vec![("assigned_id", "u16".to_string())]));
+        assert_eq!(schema(Frame::join_request(0)), ("ControllerMessage", "JoinRequest", vec![]));
+        assert_eq!(schema(Frame::new()), ("Empty", "Empty", vec![]));
+    }
+
+    #[test]
+    fn nested_type_names() {
+        let frame = Frame::new().client_message(ClientMessage::snapshot(GameMode::Idle, 1.0, [
+            NodeInfo { id: 1, battery: 90, color: (255, 0, 0), score: 3 },
+        ]));
+        assert_eq!(schema(frame).2, vec![
+            ("mode", "GameMode".to_string()),
+            ("brightness", "f32".to_string()),
+            ("nodes", "Vec<NodeInfo>".to_string()),
+        ]);
+
+        let frame = Frame::new().message(ControllerMessage::TeamAssign { controller_id: 1, team: 0, color: (0, 0, 0) });
+        assert_eq!(schema(frame).2[2], ("color", "(u8, u8, u8)".to_string()));
+
+        let frame = Frame::control(ProtocolMessage::Compressed { algo: 1, data: vec![1, 2] });
+        assert_eq!(schema(frame).2[1], ("data", "Vec<u8>".to_string()));
+    }
+}
@@ -0,0 +1,152 @@
+//! Decoding frames from an async byte source like a socket or a serial port, available with the `tokio` feature.
+//!
+//! A [`FrameStream`] buffers whatever the reader returns until a complete frame arrived, so frames may be split over
+//! any number of reads and several frames may arrive in one read. Bytes which don't start with the magic string are
+//! reported as an error and skipped up to the next magic string, like [`Frame::decode_many`] does for captures.
+
+use core::pin::Pin;
+use core::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::frame::{frame_len, Frame, FrameError, MAGIC, PREFIX_LEN};
+
+/// How many bytes are requested from the reader at once.
+const READ_CHUNK_LEN: usize = 512;
+
+/// A stream of the frames read from an [`AsyncRead`], ending when the reader does.
+///
+/// If the reader ends in the middle of a frame, the stream yields `FrameError::TooShort` before ending. Read errors are
+/// yielded as `FrameError::SerializeError`, after which reading is attempted again on the next poll.
+pub struct FrameStream<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    eof:    bool,
+}
+
+impl<R: AsyncRead + Unpin> FrameStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, buffer: Vec::new(), eof: false }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// The next frame or error from the buffered bytes, or `None` if more bytes are needed first.
+    fn next_buffered(&mut self) -> Option<Result<Frame, FrameError>> {
+        match frame_len(&self.buffer) {
+            Err(FrameError::EmptyBuffer | FrameError::TooShort { .. }) => None,
+            Ok(len) if self.buffer.len() < len => None,
+            Ok(len) => {
+                let decoded = Frame::try_from_prefix(&self.buffer[.. len]).map(|(frame, _len)| frame);
+                self.buffer.drain(.. len);
+                Some(decoded)
+            },
+            Err(error) => {
+                // Keep what might be the start of the next magic string, which may still be arriving.
+                let skip = self.buffer[1 ..]
+                    .windows(MAGIC.len())
+                    .position(|window| window == MAGIC)
+                    .map_or(self.buffer.len() + 1 - MAGIC.len(), |offset| offset + 1);
+                self.buffer.drain(.. skip);
+                Some(Err(error))
+            },
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for FrameStream<R> {
+    type Item = Result<Frame, FrameError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(item) = this.next_buffered() {
+                return Poll::Ready(Some(item));
+            }
+
+            if this.eof {
+                if this.buffer.is_empty() {
+                    return Poll::Ready(None);
+                }
+
+                let expected = frame_len(&this.buffer).unwrap_or(PREFIX_LEN);
+                let actual = this.buffer.len();
+                this.buffer.clear();
+                return Poll::Ready(Some(Err(FrameError::TooShort { expected, actual })));
+            }
+
+            let mut chunk = [0; READ_CHUNK_LEN];
+            let mut read = ReadBuf::new(&mut chunk);
+            if ready!(Pin::new(&mut this.reader).poll_read(cx, &mut read)).is_err() {
+                return Poll::Ready(Some(Err(FrameError::SerializeError)));
+            }
+
+            if read.filled().is_empty() {
+                this.eof = true;
+            } else {
+                this.buffer.extend_from_slice(read.filled());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    async fn next<R: AsyncRead + Unpin>(stream: &mut FrameStream<R>) -> Option<Result<Frame, FrameError>> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test]
+    async fn two_frames_over_a_pipe() {
+        let frames = [Frame::join_request(3), Frame::tick(4).ranging(0x0102_0304_u32)];
+        let (mut writer, reader) = tokio::io::duplex(16);
+
+        let sent = frames.clone();
+        let writing = async move {
+            let mut bytes = sent[0].to_bytes().unwrap();
+            bytes.extend(sent[1].to_bytes().unwrap());
+            // Small pieces, so frames arrive over many partial reads.
+            for piece in bytes.chunks(7) {
+                writer.write_all(piece).await.unwrap();
+            }
+        };
+
+        let reading = async {
+            let mut stream = FrameStream::new(reader);
+            let mut received = Vec::new();
+            while let Some(frame) = next(&mut stream).await {
+                received.push(frame);
+            }
+            received
+        };
+
+        let ((), received) = tokio::join!(writing, reading);
+        assert_eq!(received, frames.map(Ok));
+    }
+
+    #[tokio::test]
+    async fn garbage_and_truncated_frames() {
+        let frame = Frame::tick(4);
+        let bytes = frame.to_bytes().unwrap();
+
+        let mut input = b"garbage!".to_vec();
+        input.extend_from_slice(&bytes);
+        input.extend_from_slice(&bytes[.. 20]);
+
+        let mut stream = FrameStream::new(&input[..]);
+        assert!(matches!(next(&mut stream).await, Some(Err(FrameError::NoMagicString(_)))));
+        assert_eq!(next(&mut stream).await, Some(Ok(frame)));
+        assert_eq!(next(&mut stream).await, Some(Err(FrameError::TooShort { expected: bytes.len(), actual: 20 })));
+        assert_eq!(next(&mut stream).await, None);
+    }
+}
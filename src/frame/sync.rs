@@ -0,0 +1,105 @@
+//! Monitoring of the tick synchronization between nodes and their master, based on `ClockDrift` reports.
+
+use std::collections::BTreeMap;
+
+use crate::frame::{Frame, FramePayload, ProtocolMessage};
+
+/// The drift samples reported by a single node.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DriftStats {
+    pub samples: u32,
+    /// The drift of the most recent sample.
+    pub last:    i32,
+    /// The drift furthest away from zero, in either direction.
+    pub worst:   i32,
+    sum:         i64,
+}
+
+impl DriftStats {
+    /// The average drift over all samples, rounded towards zero.
+    pub fn mean(&self) -> i32 {
+        (self.sum / self.samples as i64) as i32
+    }
+}
+
+/// Collects drift samples per node, to find nodes whose tick runs off.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct DriftMonitor {
+    nodes: BTreeMap<u16, DriftStats>,
+}
+
+impl DriftMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a drift sample observed for the given node.
+    pub fn record(&mut self, node_id: u16, drift: i32) {
+        let stats = self.nodes.entry(node_id).or_insert(DriftStats { samples: 0, last: 0, worst: 0, sum: 0 });
+
+        stats.samples += 1;
+        stats.last = drift;
+        stats.sum += drift as i64;
+        if drift.unsigned_abs() > stats.worst.unsigned_abs() {
+            stats.worst = drift;
+        }
+    }
+
+    /// Record the drift of a `ClockDrift` report for its sender, returning whether the frame was one.
+    pub fn on_frame(&mut self, frame: &Frame) -> bool {
+        match &frame.payload {
+            FramePayload::ProtocolMessage(ProtocolMessage::ClockDrift { drift, .. }) => {
+                self.record(frame.header.sender_id, *drift);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// The samples reported for the given node, if there are any.
+    pub fn stats(&self, node_id: u16) -> Option<&DriftStats> {
+        self.nodes.get(&node_id)
+    }
+
+    /// The node with the largest drift in either direction and that drift, preferring the lowest ID on ties.
+    pub fn worst_drift(&self) -> Option<(u16, i32)> {
+        self.nodes
+            .iter()
+            .map(|(&node_id, stats)| (node_id, stats.worst))
+            .fold(None, |worst: Option<(u16, i32)>, (node_id, drift)| match worst {
+                Some((_, worst_drift)) if worst_drift.unsigned_abs() >= drift.unsigned_abs() => worst,
+                _ => Some((node_id, drift)),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_across_tick_wrap() {
+        assert!(matches!(ProtocolMessage::clock_drift(2, u16::MAX - 2), ProtocolMessage::ClockDrift { drift: 5, .. }));
+        assert!(matches!(ProtocolMessage::clock_drift(u16::MAX - 2, 2), ProtocolMessage::ClockDrift { drift: -5, .. }));
+        assert!(matches!(ProtocolMessage::clock_drift(100, 100), ProtocolMessage::ClockDrift { drift: 0, .. }));
+    }
+
+    #[test]
+    fn worst_drift_over_nodes() {
+        let mut monitor = DriftMonitor::new();
+        assert_eq!(monitor.worst_drift(), None);
+
+        let report = |sender_id, observed_tick, expected_tick| {
+            Frame::new().protocol_message(ProtocolMessage::clock_drift(observed_tick, expected_tick)).sender_id(sender_id)
+        };
+        assert!(monitor.on_frame(&report(1, 3, u16::MAX)));
+        assert!(monitor.on_frame(&report(2, u16::MAX - 6, 1)));
+        assert!(monitor.on_frame(&report(1, 10, 10)));
+        assert!(!monitor.on_frame(&Frame::tick(7).sender_id(3)));
+
+        assert_eq!(monitor.worst_drift(), Some((2, -8)));
+        let stats = monitor.stats(1).unwrap();
+        assert_eq!((stats.samples, stats.last, stats.worst, stats.mean()), (2, 0, 4, 2));
+        assert_eq!(monitor.stats(3), None);
+    }
+}
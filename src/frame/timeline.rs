@@ -0,0 +1,124 @@
+//! Ordering of captured frames into the sequence they were sent in, for replaying a capture.
+//!
+//! Frames are ordered by their RFC 3339 timestamp first, which is comparable across the whole capture but only as
+//! precise as the clock of each sender. Frames with equal timestamps are ordered by their tick, which is precise but
+//! wraps around. Ticks are unwrapped in capture order, assuming that frames captured one after the other are less than
+//! half a tick cycle apart, so a tick of 3 captured after one of 65534 counts as 5 ticks later than it.
+//!
+//! Frames whose timestamp doesn't parse are ordered after all others, by tick only. Frames which are equal in both
+//! keep their capture order.
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::frame::Frame;
+use crate::mesh::tick;
+
+/// Sort captured frames into the order they were sent in, see the [module documentation](self).
+pub fn sort_frames(frames: &mut [Frame]) {
+    let keys = sort_keys(frames);
+    let mut order: Vec<usize> = (0 .. frames.len()).collect();
+    order.sort_by_key(|&index| keys[index]);
+
+    let mut sorted: Vec<Frame> = order.iter().map(|&index| frames[index].clone()).collect();
+    frames.swap_with_slice(&mut sorted);
+}
+
+/// The frames of a capture in the order they were sent in, each with its index in the capture.
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    frames: std::vec::IntoIter<(usize, Frame)>,
+}
+
+impl Timeline {
+    pub fn new(frames: impl IntoIterator<Item = Frame>) -> Self {
+        let frames: Vec<Frame> = frames.into_iter().collect();
+        let keys = sort_keys(&frames);
+
+        let mut indexed: Vec<(usize, Frame)> = frames.into_iter().enumerate().collect();
+        indexed.sort_by_key(|(index, _)| keys[*index]);
+
+        Self { frames: indexed.into_iter() }
+    }
+}
+
+impl FromIterator<Frame> for Timeline {
+    fn from_iter<I: IntoIterator<Item = Frame>>(frames: I) -> Self {
+        Self::new(frames)
+    }
+}
+
+impl Iterator for Timeline {
+    type Item = (usize, Frame);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Timeline {}
+
+/// Whether the timestamp is missing, the timestamp, the unwrapped tick and the capture index of every frame.
+type SortKey = (bool, Option<DateTime<FixedOffset>>, i64, usize);
+
+fn sort_keys(frames: &[Frame]) -> Vec<SortKey> {
+    let mut unwrapped = 0_i64;
+    let mut previous = None;
+
+    frames
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            let tick = frame.header.current_tick;
+            if let Some(previous) = previous {
+                unwrapped += tick::offset(previous, tick) as i64;
+            }
+            previous = Some(tick);
+
+            let timestamp = frame.header.origin_timestamp().ok();
+            (timestamp.is_none(), timestamp, unwrapped, index)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(timestamp: &str, tick: u16) -> Frame {
+        let mut frame = Frame::tick(tick);
+        frame.header.timestamp = timestamp.to_string();
+        frame
+    }
+
+    #[test]
+    fn wrapped_ticks_with_monotonic_timestamps() {
+        let capture = vec![
+            frame("2024-05-01T12:00:01+00:00", 0),
+            frame("2024-05-01T12:00:00+00:00", 65534),
+            // In another time zone, but the same instant as the first frame.
+            frame("2024-05-01T14:00:01+02:00", 1),
+            frame("2024-05-01T12:00:00+00:00", 65535),
+            frame("2024-05-01T12:00:02+00:00", 2),
+        ];
+
+        let order: Vec<usize> = Timeline::new(capture.clone()).map(|(index, _)| index).collect();
+        assert_eq!(order, [1, 3, 0, 2, 4]);
+
+        let mut sorted = capture.clone();
+        sort_frames(&mut sorted);
+        let ticks: Vec<u16> = sorted.iter().map(|frame| frame.header.current_tick).collect();
+        assert_eq!(ticks, [65534, 65535, 0, 1, 2]);
+    }
+
+    #[test]
+    fn unparseable_timestamps_come_last() {
+        let capture = vec![frame("now", 65535), frame("2024-05-01T12:00:00+00:00", 7), frame("later", 65534)];
+
+        let order: Vec<usize> = capture.into_iter().collect::<Timeline>().map(|(index, _)| index).collect();
+        assert_eq!(order, [1, 2, 0]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Counting the `ControllerMessage::Vote`s of players for decisions of cooperative games.
+//!
+//! Every node receiving the votes keeps its own tally. Since votes are broadcast and counted once per sender, all nodes
+//! which received the same votes agree on the result, regardless of the order the votes arrived in.
+
+use std::collections::BTreeMap;
+
+use crate::frame::{ControllerMessage, Frame};
+
+/// The votes cast per topic, at most one per sender and topic.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct VoteTally {
+    topics: BTreeMap<u8, BTreeMap<u16, u8>>,
+}
+
+impl VoteTally {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the vote of `sender_id`, returning `false` without changing the tally if it already voted on the topic.
+    pub fn cast(&mut self, sender_id: u16, topic: u8, choice: u8) -> bool {
+        let votes = self.topics.entry(topic).or_default();
+        if votes.contains_key(&sender_id) {
+            return false;
+        }
+
+        votes.insert(sender_id, choice);
+        true
+    }
+
+    /// Count the vote carried by a received frame, returning whether it was a vote which was counted.
+    pub fn on_frame(&mut self, frame: &Frame) -> bool {
+        match frame.as_controller_message() {
+            Some(ControllerMessage::Vote { topic, choice }) => self.cast(frame.header.sender_id, *topic, *choice),
+            _ => false,
+        }
+    }
+
+    /// The number of votes cast on the topic.
+    pub fn votes(&self, topic: u8) -> usize {
+        self.topics.get(&topic).map_or(0, BTreeMap::len)
+    }
+
+    /// The choice with the most votes on the topic, `None` if nobody voted. Ties are won by the lowest choice, so games
+    /// should order the choices of a topic by preference.
+    pub fn result(&self, topic: u8) -> Option<u8> {
+        let mut counts = BTreeMap::<u8, usize>::new();
+        for &choice in self.topics.get(&topic)?.values() {
+            *counts.entry(choice).or_default() += 1;
+        }
+
+        // `max_by_key` returns the last maximum, so iterate from the highest choice down.
+        counts.into_iter().rev().max_by_key(|&(_, count)| count).map(|(choice, _)| choice)
+    }
+
+    /// Forget all votes on the topic, for deciding it again.
+    pub fn clear(&mut self, topic: u8) {
+        self.topics.remove(&topic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tallying() {
+        let mut tally = VoteTally::new();
+        assert_eq!(tally.result(1), None);
+
+        assert!(tally.on_frame(&Frame::vote(1, 3).sender_id(4)));
+        assert!(tally.on_frame(&Frame::vote(1, 2).sender_id(5)));
+        assert!(tally.on_frame(&Frame::vote(1, 3).sender_id(6)));
+        assert!(tally.cast(4, 2, 9));
+        assert!(!tally.on_frame(&Frame::tick(1).sender_id(7)));
+
+        assert_eq!(tally.votes(1), 3);
+        assert_eq!(tally.result(1), Some(3));
+        assert_eq!(tally.result(2), Some(9));
+
+        tally.clear(1);
+        assert_eq!(tally.result(1), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_lowest_choice() {
+        let mut tally = VoteTally::new();
+        for (sender_id, choice) in [(1, 7), (2, 4), (3, 7), (4, 4), (5, 9)] {
+            tally.cast(sender_id, 0, choice);
+        }
+
+        assert_eq!(tally.result(0), Some(4));
+    }
+
+    #[test]
+    fn double_votes_are_rejected() {
+        let mut tally = VoteTally::new();
+        assert!(tally.cast(4, 1, 3));
+        assert!(!tally.cast(4, 1, 2));
+        assert!(!tally.on_frame(&Frame::vote(1, 2).sender_id(4)));
+
+        assert_eq!(tally.votes(1), 1);
+        assert_eq!(tally.result(1), Some(3));
+    }
+}
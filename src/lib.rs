@@ -6,13 +6,20 @@
 pub mod client;
 pub mod packet;
 pub mod frame;
+pub mod mesh;
+pub mod wire;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 
+pub use self::client::{ClientFrame, ClientHeader};
 pub use self::packet::{UwbPacket, UwbMessage, GameMode};
 pub use self::frame::{
     Frame,
     FrameError,
     FrameHeader,
     FramePayload,
+    MASTER_ID,
+    GLOBAL_UNIVERSE,
     ClientMessage,
     ControllerMessage,
     InternalMessage,
@@ -0,0 +1,380 @@
+//! Bookkeeping for frames sent with `requires_acknowledgement`, retrying them until they are acknowledged.
+//!
+//! A frame counts as acknowledged when the receiver answers with a dedicated `ProtocolMessage::Acknowledged` frame, or
+//! when it piggybacks the acknowledgement onto any other frame it sends back via [`Frame::also_acks`]. A receiver
+//! acknowledging several frames in quick succession may also answer with a single `ProtocolMessage::AcknowledgedBatch`.
+//! All are handled identically by [`AckManager::on_frame`], which also stops retrying frames whose transaction the
+//! receiver cancelled with a `ProtocolMessage::Abort`. Aborts from nodes other than the target of a frame are ignored.
+//!
+//! Every acknowledgement and timeout of a frame with a `target_id` is also recorded in the [`LinkQuality`] of the
+//! manager, treating the target as the neighbor the frame was sent to.
+
+use crate::frame::{Frame, FrameKind, FramePayload, ProtocolMessage};
+use crate::mesh::stats::LinkQuality;
+use crate::mesh::tick;
+
+/// The number of ticks to wait for an acknowledgement before sending a frame again, unless the frame asks for a
+/// different timeout with [`Frame::ack_timeout`].
+pub const DEFAULT_ACK_TIMEOUT_TICKS: u16 = 100;
+
+/// The number of transmissions of a frame, including the first one, before giving up on it.
+pub const DEFAULT_MAX_ATTEMPTS: u8 = 5;
+
+/// The number of transmissions control frames, those carrying a `ProtocolMessage`, get on top of `max_attempts`,
+/// since the mesh itself stalls when they are lost.
+pub const CONTROL_EXTRA_ATTEMPTS: u8 = 2;
+
+/// The most message IDs one `ProtocolMessage::AcknowledgedBatch` may carry, which keeps the encoded list of the
+/// 10-character message IDs of fresh frames below 300 bytes. Receivers ignore the IDs beyond it.
+pub const MAX_ACK_BATCH: usize = 16;
+
+/// What to do with a pending frame whose acknowledgement timed out, as returned by [`AckManager::poll`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum AckAction {
+    /// Send the frame again, keeping its message ID so the receiver can detect the duplicate.
+    Retry(Frame),
+    /// The frame was sent `max_attempts` times, or [`CONTROL_EXTRA_ATTEMPTS`] more for control frames, without being
+    /// acknowledged and is no longer tracked.
+    GaveUp(Frame),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Pending {
+    frame:    Frame,
+    sent_at:  u16,
+    attempts: u8,
+}
+
+/// Tracks frames awaiting an acknowledgement and decides when to retry them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AckManager {
+    timeout_ticks: u16,
+    max_attempts:  u8,
+    pending:       Vec<Pending>,
+    links:         LinkQuality,
+}
+
+impl AckManager {
+    pub fn new() -> Self {
+        Self {
+            timeout_ticks: DEFAULT_ACK_TIMEOUT_TICKS,
+            max_attempts:  DEFAULT_MAX_ATTEMPTS,
+            pending:       Vec::new(),
+            links:         LinkQuality::new(),
+        }
+    }
+
+    pub fn timeout_ticks(mut self, ticks: u16) -> Self {
+        self.timeout_ticks = ticks;
+        self
+    }
+
+    pub fn max_attempts(mut self, attempts: u8) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Start tracking a frame which was just sent, if it requires an acknowledgement.
+    pub fn track(&mut self, frame: Frame, now_tick: u16) {
+        if frame.header.requires_acknowledgement {
+            self.pending.push(Pending { frame, sent_at: now_tick, attempts: 1 });
+        }
+    }
+
+    /// Replace the link statistics fed by this manager, for example to use a different weight.
+    pub fn link_quality(mut self, links: LinkQuality) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Stop tracking the frame with the given message ID, returning whether it was pending.
+    pub fn acknowledge(&mut self, message_id: &str) -> bool {
+        let Some(index) = self.pending.iter().position(|pending| pending.frame.header.message_id == message_id) else {
+            return false;
+        };
+
+        if let Some(target_id) = self.pending.remove(index).frame.header.target_id {
+            self.links.on_ack(target_id);
+        }
+        true
+    }
+
+    /// Handle the acknowledgements carried by a received frame, returning how many pending frames were acknowledged.
+    /// Frames dropped because of an `Abort` are not counted.
+    pub fn on_frame(&mut self, frame: &Frame) -> usize {
+        let mut acknowledged = 0;
+
+        match &frame.payload {
+            FramePayload::ProtocolMessage(ProtocolMessage::Acknowledged { message_id }) => {
+                acknowledged += self.acknowledge(message_id) as usize;
+            },
+            FramePayload::ProtocolMessage(ProtocolMessage::AcknowledgedBatch { message_ids }) => {
+                for message_id in message_ids.iter().take(MAX_ACK_BATCH) {
+                    acknowledged += self.acknowledge(message_id) as usize;
+                }
+            },
+            // Only the target of a frame may cancel it, broadcasts may be cancelled by anyone who received them.
+            FramePayload::ProtocolMessage(ProtocolMessage::Abort { message_id, .. }) => {
+                self.pending.retain(|pending| {
+                    pending.frame.header.message_id != *message_id
+                        || pending.frame.header.target_id.is_some_and(|target_id| target_id != frame.header.sender_id)
+                });
+            },
+            _ => {},
+        }
+        if let Some(message_id) = &frame.header.acked_message_id {
+            acknowledged += self.acknowledge(message_id) as usize;
+        }
+
+        acknowledged
+    }
+
+    /// Collect the frames whose acknowledgement timed out at the given tick.
+    pub fn poll(&mut self, now_tick: u16) -> Vec<AckAction> {
+        let mut actions = Vec::new();
+        let (timeout_ticks, max_attempts, links) = (self.timeout_ticks, self.max_attempts, &mut self.links);
+
+        self.pending.retain_mut(|pending| {
+            let timeout_ticks = pending.frame.expect_ack_within().unwrap_or(timeout_ticks);
+            if tick::elapsed(pending.sent_at, now_tick) < timeout_ticks {
+                return true;
+            }
+
+            if let Some(target_id) = pending.frame.header.target_id {
+                links.on_timeout(target_id);
+            }
+
+            if pending.attempts >= attempts_for(max_attempts, &pending.frame) {
+                actions.push(AckAction::GaveUp(pending.frame.clone()));
+                false
+            } else {
+                pending.attempts += 1;
+                pending.sent_at = now_tick;
+                actions.push(AckAction::Retry(pending.frame.clone()));
+                true
+            }
+        });
+
+        actions
+    }
+
+    pub fn is_pending(&self, message_id: &str) -> bool {
+        self.pending.iter().any(|pending| pending.frame.header.message_id == message_id)
+    }
+
+    /// The quality of the links to the targets of tracked frames, from their acknowledgements and timeouts.
+    pub fn links(&self) -> &LinkQuality {
+        &self.links
+    }
+
+    /// The number of frames still awaiting an acknowledgement.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// The number of times this manager sends the frame in the worst case, see [`Frame::estimated_retries`].
+    pub fn estimated_retries(&self, frame: &Frame) -> u8 {
+        if frame.header.requires_acknowledgement {
+            attempts_for(self.max_attempts, frame)
+        } else {
+            0
+        }
+    }
+}
+
+/// The number of transmissions of a tracked frame before giving up on it.
+fn attempts_for(max_attempts: u8, frame: &Frame) -> u8 {
+    match frame.payload.kind() {
+        FrameKind::Protocol => max_attempts.saturating_add(CONTROL_EXTRA_ATTEMPTS),
+        _ => max_attempts,
+    }
+}
+
+impl Frame {
+    /// The number of ticks the sender of this frame waits for an acknowledgement, if it asked for a specific timeout
+    /// with [`Frame::ack_timeout`]. `None` for frames which don't require an acknowledgement.
+    pub fn expect_ack_within(&self) -> Option<u16> {
+        self.header.ack_timeout_ticks.filter(|_| self.header.requires_acknowledgement)
+    }
+
+    /// The number of transmissions this frame may incur in the worst case, for budgeting airtime together with
+    /// [`Frame::estimate_airtime`].
+    ///
+    /// Frames without `requires_acknowledgement` are sent once and never retried, which gives zero. Frames requiring one
+    /// count every transmission including the first, like `max_attempts`. They are assumed to be tracked by an
+    /// [`AckManager`] with the default [`DEFAULT_MAX_ATTEMPTS`] and to never be acknowledged, so they are sent that many
+    /// times, or [`CONTROL_EXTRA_ATTEMPTS`] more for control frames. Use [`AckManager::estimated_retries`] for managers
+    /// with a different limit.
+    pub fn estimated_retries(&self) -> u8 {
+        AckManager::new().estimated_retries(self)
+    }
+}
+
+impl Default for AckManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::AbortReason;
+
+    #[test]
+    fn standalone_ack_clears_retry_queue() {
+        let mut acks = AckManager::new();
+        let frame = Frame::join_request(0);
+        let id = frame.header.message_id.clone();
+        acks.track(frame, 0);
+
+        let ack = Frame::new().protocol_message(ProtocolMessage::Acknowledged { message_id: id.clone() });
+        assert_eq!(acks.on_frame(&ack), 1);
+        assert!(!acks.is_pending(&id));
+        assert!(acks.poll(DEFAULT_ACK_TIMEOUT_TICKS).is_empty());
+    }
+
+    #[test]
+    fn batch_ack_clears_several_entries() {
+        let mut acks = AckManager::new();
+        let frames = [Frame::join_request(0), Frame::join_request(1), Frame::join_request(2)];
+        for frame in &frames {
+            acks.track(frame.clone(), 0);
+        }
+
+        let message_ids = vec![frames[0].header.message_id.clone(), frames[2].header.message_id.clone(), "V1StGXR8_Z".to_string()];
+        let ack = Frame::control(ProtocolMessage::AcknowledgedBatch { message_ids });
+        assert_eq!(acks.on_frame(&ack), 2);
+        assert_eq!(acks.pending_len(), 1);
+        assert!(acks.is_pending(&frames[1].header.message_id));
+        assert_eq!(acks.on_frame(&ack), 0);
+    }
+
+    #[test]
+    fn batch_ack_beyond_the_cap_is_ignored() {
+        let mut acks = AckManager::new();
+        let frame = Frame::join_request(0);
+        let id = frame.header.message_id.clone();
+        acks.track(frame, 0);
+
+        let mut message_ids = vec!["V1StGXR8_Z".to_string(); MAX_ACK_BATCH];
+        message_ids.push(id.clone());
+        let ack = Frame::control(ProtocolMessage::AcknowledgedBatch { message_ids });
+        assert_eq!(acks.on_frame(&ack), 0);
+        assert!(acks.is_pending(&id));
+        assert!(ack.validate().is_err());
+    }
+
+    #[test]
+    fn abort_drops_pending_frame() {
+        let mut acks = AckManager::new().timeout_ticks(10);
+        let frame = Frame::join_request(0).target_id(4);
+        let other = Frame::join_request(0);
+        acks.track(frame.clone(), 0);
+        acks.track(other.clone(), 0);
+
+        let abort = Frame::control(ProtocolMessage::Abort { message_id: frame.header.message_id.clone(), reason: AbortReason::Cancelled });
+        // A third node can't cancel the transaction with node 4.
+        assert_eq!(acks.on_frame(&abort.clone().sender_id(5)), 0);
+        assert!(acks.is_pending(&frame.header.message_id));
+
+        assert_eq!(acks.on_frame(&abort.sender_id(4)), 0);
+        assert!(!acks.is_pending(&frame.header.message_id));
+        assert_eq!(acks.poll(10), vec![AckAction::Retry(other)]);
+        // An aborted transaction says nothing about the link.
+        assert_eq!(acks.links().quality(4), 1.0);
+    }
+
+    #[test]
+    fn piggybacked_ack_clears_retry_queue() {
+        let mut acks = AckManager::new();
+        let frame = Frame::join_request(0);
+        let id = frame.header.message_id.clone();
+        acks.track(frame, 0);
+
+        let reply = Frame::join_response(1, 3).also_acks(id.clone());
+        assert_eq!(acks.on_frame(&reply), 1);
+        assert!(!acks.is_pending(&id));
+        assert!(acks.poll(DEFAULT_ACK_TIMEOUT_TICKS).is_empty());
+    }
+
+    #[test]
+    fn retries_until_giving_up() {
+        let mut acks = AckManager::new().timeout_ticks(10).max_attempts(2);
+        let frame = Frame::join_request(0);
+        acks.track(frame.clone(), u16::MAX - 5);
+        acks.track(Frame::tick(0), 0);
+
+        assert_eq!(acks.pending_len(), 1);
+        assert!(acks.poll(3).is_empty());
+        assert_eq!(acks.poll(4), vec![AckAction::Retry(frame.clone())]);
+        assert_eq!(acks.poll(14), vec![AckAction::GaveUp(frame)]);
+        assert_eq!(acks.pending_len(), 0);
+    }
+
+    #[test]
+    fn custom_timeout_overrides_the_default() {
+        let mut acks = AckManager::new().timeout_ticks(10);
+        let urgent = Frame::join_request(0).ack_timeout(2);
+        let lenient = Frame::join_request(0).ack_timeout(50);
+        let default = Frame::join_request(0);
+        for frame in [&urgent, &lenient, &default] {
+            acks.track(frame.clone(), 0);
+        }
+
+        assert_eq!(urgent.expect_ack_within(), Some(2));
+        assert_eq!(default.expect_ack_within(), None);
+        assert_eq!(Frame::tick(0).ack_timeout(2).expect_ack_within(), None);
+
+        assert_eq!(acks.poll(2), vec![AckAction::Retry(urgent.clone())]);
+        assert!(acks.acknowledge(&urgent.header.message_id));
+        assert_eq!(acks.poll(10), vec![AckAction::Retry(default.clone())]);
+        assert!(acks.acknowledge(&default.header.message_id));
+        assert!(acks.poll(49).is_empty());
+        assert_eq!(acks.poll(50), vec![AckAction::Retry(lenient)]);
+    }
+
+    #[test]
+    fn acks_and_timeouts_feed_link_quality() {
+        let mut acks = AckManager::new().timeout_ticks(10);
+        let flaky = Frame::join_request(0).target_id(4);
+        let good = Frame::join_request(0).target_id(5);
+        acks.track(flaky.clone(), 0);
+        acks.track(good.clone(), 0);
+        // Broadcasts have no single neighbor to blame.
+        acks.track(Frame::tick(0).require_confirmation(), 0);
+
+        assert!(acks.acknowledge(&good.header.message_id));
+        assert_eq!(acks.poll(10).len(), 2);
+        assert_eq!(acks.poll(20).len(), 2);
+        assert!(acks.links().quality(4) < 1.0);
+        assert_eq!(acks.links().quality(5), 1.0);
+        assert_eq!(acks.links().iter().count(), 2);
+
+        let low = acks.links().quality(4);
+        assert!(acks.acknowledge(&flaky.header.message_id));
+        assert!(acks.links().quality(4) > low);
+    }
+
+    #[test]
+    fn estimated_retries() {
+        assert_eq!(Frame::tick(0).estimated_retries(), 0);
+        assert_eq!(Frame::join_request(0).estimated_retries(), DEFAULT_MAX_ATTEMPTS);
+        assert_eq!(Frame::tick(0).require_confirmation().estimated_retries(), DEFAULT_MAX_ATTEMPTS + CONTROL_EXTRA_ATTEMPTS);
+        assert_eq!(AckManager::new().max_attempts(2).estimated_retries(&Frame::join_request(0)), 2);
+        assert_eq!(AckManager::new().max_attempts(2).estimated_retries(&Frame::tick(0)), 0);
+    }
+
+    #[test]
+    fn control_frames_are_retried_more() {
+        let mut acks = AckManager::new().timeout_ticks(10).max_attempts(1);
+        let control = Frame::tick(0).require_confirmation();
+        acks.track(control.clone(), 0);
+        acks.track(Frame::join_request(0), 0);
+
+        let retried = |actions: Vec<AckAction>| actions.iter().filter(|action| matches!(action, AckAction::Retry(_))).count();
+        let retries: usize = (1 ..= 5).map(|n| retried(acks.poll(n * 10))).sum();
+        assert_eq!(retries + 1, acks.estimated_retries(&control) as usize);
+        assert_eq!(acks.pending_len(), 0);
+    }
+}
@@ -0,0 +1,156 @@
+//! Negotiation of optional protocol features between peers running different firmware.
+//!
+//! Every node announces its protocol version and the features it supports in a `ProtocolMessage::Hello`. A feature
+//! may only be used towards a peer once both sides advertised it, so frames encrypted or fragmented for a peer which
+//! can't decode them are never sent. Bits unknown to this version are kept, but never end up in a negotiated set.
+
+use core::ops::BitOr;
+use std::collections::BTreeMap;
+
+use crate::frame::{Frame, ProtocolMessage};
+
+/// The version of the protocol announced in `Hello`s by this build.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// A set of optional protocol features, as carried in the `capabilities` bitfield of `ProtocolMessage::Hello`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Capabilities(pub u32);
+
+impl Capabilities {
+    pub const NONE: Self = Self(0);
+    /// Decoding frames in the [compact encoding](crate::frame::compact).
+    pub const COMPACT_ENCODING: Self = Self(1 << 0);
+    /// Verifying Ed25519 frame signatures.
+    pub const SIGNATURES: Self = Self(1 << 1);
+    /// Splitting `FramePayload::Batch`es.
+    pub const BATCHING: Self = Self(1 << 2);
+    /// Decrypting `ProtocolMessage::Encrypted` payloads.
+    pub const ENCRYPTION: Self = Self(1 << 3);
+    /// Reassembling fragmented frames.
+    pub const FRAGMENTATION: Self = Self(1 << 4);
+    /// Decompressing `ProtocolMessage::Compressed` payloads.
+    pub const COMPRESSION: Self = Self(1 << 5);
+
+    /// The features supported by this build of the crate.
+    pub fn this_build() -> Self {
+        let mut capabilities = Self::COMPACT_ENCODING | Self::BATCHING;
+
+        if cfg!(feature = "crypto") {
+            capabilities = capabilities | Self::SIGNATURES;
+        }
+        if cfg!(feature = "compression") {
+            capabilities = capabilities | Self::COMPRESSION;
+        }
+        capabilities
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether all features of `flag` are in the set.
+    pub fn supports(self, flag: Self) -> bool {
+        self.0 & flag.0 == flag.0
+    }
+
+    /// The features supported by both sets.
+    pub fn common(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// A `Hello` announcing these features with the version of this build.
+    pub fn hello(self) -> ProtocolMessage {
+        ProtocolMessage::Hello { version: PROTOCOL_VERSION, capabilities: self.0 }
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Remembers the `Hello` of every peer, to decide which features may be used towards it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PeerCapabilities {
+    ours:  Capabilities,
+    peers: BTreeMap<u16, (u8, Capabilities)>,
+}
+
+impl PeerCapabilities {
+    /// Negotiate with the given features of this node.
+    pub fn new(ours: Capabilities) -> Self {
+        Self { ours, peers: BTreeMap::new() }
+    }
+
+    pub fn on_hello(&mut self, peer_id: u16, version: u8, capabilities: u32) {
+        self.peers.insert(peer_id, (version, Capabilities(capabilities)));
+    }
+
+    /// Record the `Hello` carried by a received frame, returning whether it was one.
+    pub fn on_frame(&mut self, frame: &Frame) -> bool {
+        match frame.as_protocol_message() {
+            Some(ProtocolMessage::Hello { version, capabilities }) => {
+                self.on_hello(frame.header.sender_id, *version, *capabilities);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Forget a peer, for example once it left the mesh, since it may come back with different firmware.
+    pub fn forget(&mut self, peer_id: u16) {
+        self.peers.remove(&peer_id);
+    }
+
+    /// The protocol version the peer announced, `None` if it never sent a `Hello`.
+    pub fn version(&self, peer_id: u16) -> Option<u8> {
+        self.peers.get(&peer_id).map(|(version, _)| *version)
+    }
+
+    /// The features both this node and the peer support, none for peers which never sent a `Hello`.
+    pub fn negotiated(&self, peer_id: u16) -> Capabilities {
+        self.peers.get(&peer_id).map_or(Capabilities::NONE, |(_, theirs)| self.ours.common(*theirs))
+    }
+
+    /// Whether the features of `flag` may be used towards the peer.
+    pub fn supports(&self, peer_id: u16, flag: Capabilities) -> bool {
+        self.negotiated(peer_id).supports(flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn hello_round_trip() {
+        let capabilities = Capabilities::COMPACT_ENCODING | Capabilities::FRAGMENTATION;
+        let frame = Frame::hello(capabilities).sender_id(4);
+        let decoded = Frame::try_from(&frame.to_bytes().unwrap()[..]).unwrap();
+
+        assert_eq!(decoded, frame);
+        assert_eq!(decoded.as_protocol_message(), Some(&ProtocolMessage::Hello { version: PROTOCOL_VERSION, capabilities: 0b10001 }));
+    }
+
+    #[test]
+    fn negotiation() {
+        let mut peers = PeerCapabilities::new(Capabilities::COMPACT_ENCODING | Capabilities::ENCRYPTION);
+        assert!(peers.on_frame(&Frame::hello(Capabilities::ENCRYPTION | Capabilities::FRAGMENTATION).sender_id(4)));
+        // A newer peer advertising a feature this version doesn't know yet.
+        peers.on_hello(5, 2, Capabilities::COMPACT_ENCODING.bits() | 1 << 31);
+
+        assert!(peers.supports(4, Capabilities::ENCRYPTION));
+        assert!(!peers.supports(4, Capabilities::FRAGMENTATION));
+        assert!(!peers.supports(4, Capabilities::COMPACT_ENCODING | Capabilities::ENCRYPTION));
+        assert_eq!(peers.negotiated(5), Capabilities::COMPACT_ENCODING);
+        assert_eq!(peers.version(5), Some(2));
+
+        assert!(!peers.supports(6, Capabilities::ENCRYPTION));
+        peers.forget(4);
+        assert!(!peers.supports(4, Capabilities::ENCRYPTION));
+    }
+}
@@ -0,0 +1,125 @@
+//! Adaptive pacing of transmissions under heavy load, backing off exponentially after collisions.
+//!
+//! Every reported collision doubles the backoff window, up to a maximum, while every successful transmission halves it
+//! again. After each outcome, a node waits a random number of ticks within the current window before it transmits,
+//! so nodes which collided once are unlikely to collide again. The randomness is seeded with the node ID, keeping the
+//! behaviour of a node reproducible without a random number generator on the controller.
+
+/// The smallest backoff window by default, in ticks. A window of one tick means no backoff at all.
+pub const DEFAULT_MIN_WINDOW: u16 = 1;
+
+/// The largest backoff window by default, in ticks.
+pub const DEFAULT_MAX_WINDOW: u16 = 256;
+
+/// Tracks the backoff window of a node from the outcome of its transmissions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BackoffController {
+    min_window: u16,
+    max_window: u16,
+    window:     u16,
+    delay:      u16,
+    rng:        u32,
+}
+
+impl BackoffController {
+    pub fn new(node_id: u16) -> Self {
+        Self {
+            min_window: DEFAULT_MIN_WINDOW,
+            max_window: DEFAULT_MAX_WINDOW,
+            window:     DEFAULT_MIN_WINDOW,
+            delay:      0,
+            // Xorshift must not be seeded with zero.
+            rng:        (node_id as u32) ^ 0x9E37_79B9,
+        }
+    }
+
+    /// Set the smallest backoff window in ticks, at least one.
+    pub fn min_window(mut self, ticks: u16) -> Self {
+        self.min_window = ticks.max(1);
+        self.max_window = self.max_window.max(self.min_window);
+        self.window = self.window.clamp(self.min_window, self.max_window);
+        self
+    }
+
+    /// Set the largest backoff window in ticks, at least the smallest one.
+    pub fn max_window(mut self, ticks: u16) -> Self {
+        self.max_window = ticks.max(self.min_window);
+        self.window = self.window.clamp(self.min_window, self.max_window);
+        self
+    }
+
+    /// Record a collision of the last transmission, doubling the backoff window.
+    pub fn on_collision(&mut self) {
+        self.window = self.window.saturating_mul(2).min(self.max_window);
+        self.draw_delay();
+    }
+
+    /// Record a successful transmission, halving the backoff window.
+    pub fn on_success(&mut self) {
+        self.window = (self.window / 2).max(self.min_window);
+        self.draw_delay();
+    }
+
+    /// The current backoff window in ticks.
+    pub fn window(&self) -> u16 {
+        self.window
+    }
+
+    /// The earliest tick at which a node wanting to transmit at `now_tick` may do so, wrapping around with the tick counter.
+    pub fn next_send_allowed(&self, now_tick: u16) -> u16 {
+        now_tick.wrapping_add(self.delay)
+    }
+
+    fn draw_delay(&mut self) {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.delay = (self.rng % self.window as u32) as u16;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collisions_widen_and_successes_shrink_the_window() {
+        let mut backoff = BackoffController::new(3).max_window(64);
+        assert_eq!(backoff.window(), 1);
+        assert_eq!(backoff.next_send_allowed(100), 100);
+
+        for expected in [2, 4, 8, 16, 32, 64, 64] {
+            backoff.on_collision();
+            assert_eq!(backoff.window(), expected);
+            let delay = backoff.next_send_allowed(100) - 100;
+            assert!(delay < expected);
+        }
+
+        backoff.on_success();
+        assert_eq!(backoff.window(), 32);
+        for _ in 0 .. 10 {
+            backoff.on_success();
+        }
+        assert_eq!(backoff.window(), 1);
+        assert_eq!(backoff.next_send_allowed(100), 100);
+    }
+
+    #[test]
+    fn next_send_allowed_wraps_around() {
+        let mut backoff = BackoffController::new(9).min_window(1000);
+        backoff.on_collision();
+
+        let allowed = backoff.next_send_allowed(u16::MAX - 10);
+        assert!(crate::mesh::tick::elapsed(u16::MAX - 10, allowed) < 2000);
+    }
+
+    #[test]
+    fn nodes_draw_different_delays() {
+        let delays = |node_id| {
+            let mut backoff = BackoffController::new(node_id);
+            (0 .. 8).map(|_| { backoff.on_collision(); backoff.next_send_allowed(0) }).collect::<Vec<_>>()
+        };
+
+        assert_ne!(delays(1), delays(2));
+    }
+}
@@ -0,0 +1,140 @@
+//! Collection and ranking of `DiscoverReply` messages received after broadcasting a `Discover` frame.
+//!
+//! A joining node broadcasts [`Frame::discover`] and records every reply arriving within a window of ticks. Once the
+//! window has passed, the candidates are ranked to pick the master the node should send its `JoinRequest` to.
+
+use crate::frame::{Frame, FramePayload, ControllerMessage};
+use crate::mesh::tick;
+
+/// A node which answered a `Discover` broadcast.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DiscoveryCandidate {
+    /// The assigned ID of the replying node.
+    pub node_id: u16,
+    /// Whether the replying node is a master node accepting `JoinRequest`s.
+    pub is_master: bool,
+    /// The universe the replying node is currently part of.
+    pub universe: u8,
+    /// How busy the replying node is, from 0 (idle) to 255 (fully loaded).
+    pub load: u8,
+}
+
+/// Collects discovery replies over a window of ticks and ranks the candidates.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Discovery {
+    /// The universe the joining node wants to play in.
+    universe:   u8,
+    /// The tick at which the `Discover` broadcast was sent.
+    started_at: u16,
+    /// How many ticks after `started_at` replies are still accepted.
+    window:     u16,
+    candidates: Vec<DiscoveryCandidate>,
+}
+
+impl Discovery {
+    pub fn new(universe: u8, started_at: u16, window: u16) -> Self {
+        Self {
+            universe,
+            started_at,
+            window,
+            candidates: Vec::new(),
+        }
+    }
+
+    /// Whether the reply window has passed at the given tick.
+    pub fn is_finished(&self, now_tick: u16) -> bool {
+        tick::elapsed(self.started_at, now_tick) > self.window
+    }
+
+    /// Record a reply received at `now_tick`, returning `false` if it arrived after the window closed.
+    ///
+    /// A second reply from the same node replaces the first one, so the ranking always reflects the latest load.
+    pub fn on_reply(&mut self, now_tick: u16, candidate: DiscoveryCandidate) -> bool {
+        if self.is_finished(now_tick) {
+            return false;
+        }
+
+        match self.candidates.iter_mut().find(|c| c.node_id == candidate.node_id) {
+            Some(existing) => *existing = candidate,
+            None => self.candidates.push(candidate),
+        }
+
+        true
+    }
+
+    /// Record the reply carried by a frame received at `now_tick`, like [`Discovery::on_reply`].
+    ///
+    /// The window is measured on the local clock, so the tick of the frame, which is the clock of the replying node,
+    /// is ignored. Returns `false` if the frame is not a `DiscoverReply` or arrived after the window closed.
+    pub fn on_frame(&mut self, now_tick: u16, frame: &Frame) -> bool {
+        match frame.payload {
+            FramePayload::ControllerMessage(ControllerMessage::DiscoverReply { node_id, is_master, universe, load }) => {
+                self.on_reply(now_tick, DiscoveryCandidate { node_id, is_master, universe, load })
+            },
+            _ => false,
+        }
+    }
+
+    /// All masters in the wanted universe, ordered from the best to the worst choice.
+    ///
+    /// Masters with a lower load are preferred, ties are broken by the lower node ID.
+    pub fn ranked(&self) -> Vec<&DiscoveryCandidate> {
+        let mut masters: Vec<&DiscoveryCandidate> = self.candidates
+            .iter()
+            .filter(|c| c.is_master && c.universe == self.universe)
+            .collect();
+        masters.sort_by_key(|c| (c.load, c.node_id));
+        masters
+    }
+
+    /// The ID of the best master to send a `JoinRequest` to, if any master replied.
+    pub fn best_master(&self) -> Option<u16> {
+        self.ranked().first().map(|c| c.node_id)
+    }
+
+    /// Every node which replied within the window, including peers which are not masters.
+    pub fn candidates(&self) -> &[DiscoveryCandidate] {
+        &self.candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::MASTER_ID;
+
+    #[test]
+    fn ranks_least_loaded_master_in_universe() {
+        let mut discovery = Discovery::new(1, 100, 50);
+
+        assert!(discovery.on_frame(110, &Frame::discover_reply(110, MASTER_ID, true, 1, 200)));
+        assert!(discovery.on_frame(120, &Frame::discover_reply(120, 4, true, 1, 20)));
+        assert!(discovery.on_frame(120, &Frame::discover_reply(120, 5, true, 2, 0)));
+        assert!(discovery.on_frame(130, &Frame::discover_reply(130, 6, false, 1, 0)));
+
+        let ranked: Vec<u16> = discovery.ranked().iter().map(|c| c.node_id).collect();
+        assert_eq!(ranked, vec![4, MASTER_ID]);
+        assert_eq!(discovery.best_master(), Some(4));
+        assert_eq!(discovery.candidates().len(), 4);
+    }
+
+    #[test]
+    fn rejects_replies_after_window_across_tick_wrap() {
+        let mut discovery = Discovery::new(0, u16::MAX - 10, 20);
+
+        assert!(discovery.on_frame(5, &Frame::discover_reply(5, MASTER_ID, true, 0, 0)));
+        assert!(!discovery.on_frame(10, &Frame::discover_reply(10, 1, true, 0, 0)));
+        assert!(discovery.is_finished(10));
+        assert_eq!(discovery.best_master(), Some(MASTER_ID));
+    }
+
+    #[test]
+    fn window_follows_the_local_clock() {
+        let mut discovery = Discovery::new(0, 100, 20);
+
+        // The clock of the first master lags far behind, the one of the second is ahead.
+        assert!(discovery.on_frame(110, &Frame::discover_reply(3, MASTER_ID, true, 0, 0)));
+        assert!(!discovery.on_frame(130, &Frame::discover_reply(110, 4, true, 0, 0)));
+        assert_eq!(discovery.best_master(), Some(MASTER_ID));
+    }
+}
@@ -0,0 +1,144 @@
+//! Master election among peer controllers for meshes which lost their master, or never had a dedicated one.
+//!
+//! This is a variant of the bully algorithm in which the lowest node ID wins. A node starting an election broadcasts
+//! `ProtocolMessage::Election` with its own ID. Nodes with a lower ID answer with their own candidacy, outranking it,
+//! while nodes with a higher ID step back. Whoever was not outranked once the reply window passed announces itself
+//! with `ProtocolMessage::Coordinator`. The reply window is left to the caller, just like the tick it is measured in.
+
+use crate::frame::{Frame, ProtocolMessage};
+
+/// The election state of a single node.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Election {
+    node_id:        u16,
+    current_master: Option<u16>,
+    electing:       bool,
+    outranked:      bool,
+}
+
+impl Election {
+    pub fn new(node_id: u16) -> Self {
+        Self {
+            node_id,
+            current_master: None,
+            electing:       false,
+            outranked:      false,
+        }
+    }
+
+    /// Start an election, forgetting the current master. The returned candidacy should be broadcast.
+    pub fn start(&mut self) -> ProtocolMessage {
+        self.current_master = None;
+        self.electing = true;
+        self.outranked = false;
+
+        ProtocolMessage::Election { candidate_id: self.node_id }
+    }
+
+    /// Handle the candidacy of another node, returning this node's own candidacy if it outranks the candidate and
+    /// didn't announce it already.
+    pub fn on_election(&mut self, candidate_id: u16) -> Option<ProtocolMessage> {
+        if candidate_id < self.node_id {
+            self.outranked = true;
+            self.current_master = None;
+            self.electing = true;
+            None
+        } else if candidate_id > self.node_id && !self.electing {
+            Some(self.start())
+        } else {
+            None
+        }
+    }
+
+    /// Handle the announcement of a new master. Announcements of nodes outranked by this one are challenged with a
+    /// new election, all others are accepted.
+    pub fn on_coordinator(&mut self, master_id: u16) -> Option<ProtocolMessage> {
+        if master_id > self.node_id {
+            return Some(self.start());
+        }
+
+        self.current_master = Some(master_id);
+        self.electing = false;
+        self.outranked = false;
+        None
+    }
+
+    /// Handle a received frame, returning the message to broadcast in response, if any.
+    pub fn on_frame(&mut self, frame: &Frame) -> Option<ProtocolMessage> {
+        match frame.as_protocol_message()? {
+            ProtocolMessage::Election { candidate_id } => self.on_election(*candidate_id),
+            ProtocolMessage::Coordinator { master_id } => self.on_coordinator(*master_id),
+            _ => None,
+        }
+    }
+
+    /// End the reply window of a running election. If no other node outranked this one, it becomes the master and
+    /// the returned announcement should be broadcast.
+    pub fn conclude(&mut self) -> Option<ProtocolMessage> {
+        if !self.electing || self.outranked {
+            return None;
+        }
+
+        self.current_master = Some(self.node_id);
+        self.electing = false;
+        Some(ProtocolMessage::Coordinator { master_id: self.node_id })
+    }
+
+    /// Whether an election is running, in which this node either still is a candidate or waits for the announcement.
+    pub fn is_electing(&self) -> bool {
+        self.electing
+    }
+
+    /// The master of the mesh, or `None` while it is unknown or being elected.
+    pub fn current_master(&self) -> Option<u16> {
+        self.current_master
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_nodes_converge_on_lower_id() {
+        let (mut low, mut high) = (Election::new(2), Election::new(5));
+
+        let low_candidacy = low.start();
+        let high_candidacy = high.start();
+
+        let frame = |msg: ProtocolMessage| Frame::new().protocol_message(msg);
+        assert_eq!(high.on_frame(&frame(low_candidacy)), None);
+        assert_eq!(low.on_frame(&frame(high_candidacy)), None);
+
+        assert_eq!(high.conclude(), None);
+        let announcement = low.conclude().unwrap();
+        assert_eq!(announcement, ProtocolMessage::Coordinator { master_id: 2 });
+
+        assert_eq!(high.on_frame(&frame(announcement)), None);
+        assert_eq!(low.current_master(), Some(2));
+        assert_eq!(high.current_master(), Some(2));
+        assert!(!low.is_electing() && !high.is_electing());
+    }
+
+    #[test]
+    fn idle_node_outranking_candidate_joins_election() {
+        let mut election = Election::new(1);
+
+        assert_eq!(election.on_election(4), Some(ProtocolMessage::Election { candidate_id: 1 }));
+        assert_eq!(election.on_election(4), None);
+        assert!(election.conclude().is_some());
+    }
+
+    #[test]
+    fn coordinator_is_accepted() {
+        let mut election = Election::new(7);
+        assert_eq!(election.current_master(), None);
+
+        assert_eq!(election.on_coordinator(3), None);
+        assert_eq!(election.current_master(), Some(3));
+
+        // A node outranking the announced master challenges it.
+        assert_eq!(election.on_coordinator(9), Some(ProtocolMessage::Election { candidate_id: 7 }));
+        assert_eq!(election.current_master(), None);
+    }
+}
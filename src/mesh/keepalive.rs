@@ -0,0 +1,120 @@
+//! Liveness tracking of neighbors based on `KeepAlive` beacons with a negotiated interval.
+//!
+//! Instead of answering a fixed-rate `Ping`, every node advertises how often it beacons in `ProtocolMessage::KeepAlive`.
+//! A neighbor is considered dead once twice its advertised interval passed without hearing from it, so a single lost
+//! beacon is tolerated. Any frame received from a neighbor counts as contact, not only its beacons.
+
+use std::collections::BTreeMap;
+
+use crate::frame::{Frame, ProtocolMessage};
+use crate::mesh::tick;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Neighbor {
+    interval_ticks: u16,
+    last_seen:      u16,
+}
+
+/// Tracks when each neighbor was last heard from and how often it promised to beacon.
+///
+/// Ticks are compared modulo 2^16, so a neighbor must be checked at least once per tick cycle for its silence to be measured correctly.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct KeepAlive {
+    neighbors: BTreeMap<u16, Neighbor>,
+}
+
+impl KeepAlive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a beacon from a neighbor, updating its advertised interval.
+    pub fn on_keepalive(&mut self, neighbor_id: u16, interval_ticks: u16, now_tick: u16) {
+        self.neighbors.insert(neighbor_id, Neighbor { interval_ticks, last_seen: now_tick });
+    }
+
+    /// Record any other contact with a neighbor. Neighbors which never sent a beacon are not tracked.
+    pub fn on_contact(&mut self, neighbor_id: u16, now_tick: u16) {
+        if let Some(neighbor) = self.neighbors.get_mut(&neighbor_id) {
+            neighbor.last_seen = now_tick;
+        }
+    }
+
+    /// Record a received frame, using its sender and tick.
+    pub fn on_frame(&mut self, frame: &Frame) {
+        let (sender_id, now_tick) = (frame.header.sender_id, frame.header.current_tick);
+
+        match frame.as_protocol_message() {
+            Some(ProtocolMessage::KeepAlive { interval_ticks }) => self.on_keepalive(sender_id, *interval_ticks, now_tick),
+            _ => self.on_contact(sender_id, now_tick),
+        }
+    }
+
+    /// Whether the neighbor was heard from within twice its advertised interval. Unknown neighbors are not alive.
+    pub fn is_alive(&self, neighbor_id: u16, now_tick: u16) -> bool {
+        self.neighbors
+            .get(&neighbor_id)
+            .is_some_and(|neighbor| Self::neighbor_alive(neighbor, now_tick))
+    }
+
+    /// The IDs of all tracked neighbors which are alive at the given tick, in ascending order.
+    pub fn alive_neighbors(&self, now_tick: u16) -> Vec<u16> {
+        self.neighbors
+            .iter()
+            .filter(|(_, neighbor)| Self::neighbor_alive(neighbor, now_tick))
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// The IDs of all tracked neighbors which are considered dead at the given tick, in ascending order.
+    pub fn dead_neighbors(&self, now_tick: u16) -> Vec<u16> {
+        self.neighbors
+            .iter()
+            .filter(|(_, neighbor)| !Self::neighbor_alive(neighbor, now_tick))
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// Stop tracking all dead neighbors, returning their IDs.
+    pub fn remove_dead(&mut self, now_tick: u16) -> Vec<u16> {
+        let dead = self.dead_neighbors(now_tick);
+        for id in &dead {
+            self.neighbors.remove(id);
+        }
+        dead
+    }
+
+    fn neighbor_alive(neighbor: &Neighbor, now_tick: u16) -> bool {
+        tick::elapsed(neighbor.last_seen, now_tick) as u32 <= 2 * neighbor.interval_ticks as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbor_alive_within_twice_interval() {
+        let mut keepalive = KeepAlive::new();
+        keepalive.on_frame(&Frame::keep_alive(100, 50).sender_id(3));
+
+        assert!(keepalive.is_alive(3, 150));
+        assert!(keepalive.is_alive(3, 200));
+        assert!(!keepalive.is_alive(4, 150));
+
+        keepalive.on_frame(&Frame::join_request(190).sender_id(3));
+        assert!(keepalive.is_alive(3, 290));
+    }
+
+    #[test]
+    fn neighbor_dies_across_tick_wrap() {
+        let mut keepalive = KeepAlive::new();
+        keepalive.on_keepalive(1, 10, u16::MAX - 5);
+        keepalive.on_keepalive(2, 1000, u16::MAX - 5);
+
+        assert!(keepalive.is_alive(1, 14));
+        assert_eq!(keepalive.dead_neighbors(15), vec![1]);
+        assert_eq!(keepalive.remove_dead(15), vec![1]);
+        assert!(keepalive.is_alive(2, 15));
+    }
+}
@@ -0,0 +1,33 @@
+//! Building blocks for the behaviour of a node within the UWB mesh, on top of the frames defined in [`crate::frame`].
+//!
+//! While the frame module only describes what is sent over the air, the types in here keep track of the state a node
+//! accumulates from received frames, like the masters it discovered nearby before joining the mesh.
+
+pub mod ack;
+pub mod caps;
+pub mod congestion;
+pub mod discovery;
+pub mod election;
+pub mod keepalive;
+pub mod partition;
+pub mod role;
+pub mod router;
+pub mod schedule;
+#[cfg(feature = "sim")]
+pub mod simulator;
+pub mod stats;
+pub mod tick;
+pub mod topology;
+
+pub use self::ack::{AckManager, AckAction};
+pub use self::caps::{Capabilities, PeerCapabilities};
+pub use self::congestion::BackoffController;
+pub use self::discovery::{Discovery, DiscoveryCandidate};
+pub use self::election::Election;
+pub use self::keepalive::KeepAlive;
+pub use self::partition::PartitionDetector;
+pub use self::role::NodeRole;
+pub use self::router::{Router, ForwardPolicy, FloodPolicy, TreePolicy};
+pub use self::schedule::{Scheduler, SlotMapping};
+pub use self::stats::LinkQuality;
+pub use self::topology::MeshTopology;
@@ -0,0 +1,124 @@
+//! Detection of mesh splits, where a whole group of nodes drops out of reach at once, like when the relay connecting
+//! them to the rest of the mesh dies.
+//!
+//! Single nodes vanishing now and then is normal, since players walk away or batteries run out. Several nodes which
+//! were reachable before vanishing within a few ticks of each other are much more likely cut off by a partition, and
+//! the game state of both halves starts to diverge from that moment on.
+
+use std::collections::BTreeSet;
+
+use crate::mesh::tick;
+
+/// The number of ticks within which nodes have to vanish to be considered lost together.
+pub const DEFAULT_WINDOW_TICKS: u16 = 10;
+
+/// The number of nodes which have to vanish together to be considered a partition.
+pub const DEFAULT_MIN_GROUP: usize = 2;
+
+/// Flags groups of nodes which vanished simultaneously from the set of reachable nodes.
+///
+/// The detector is fed with the reachable nodes on every tick, like the [`KeepAlive::alive_neighbors`]. A partition
+/// persists until the lost nodes become reachable again.
+///
+/// [`KeepAlive::alive_neighbors`]: crate::mesh::KeepAlive::alive_neighbors
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PartitionDetector {
+    window_ticks: u16,
+    min_group:    usize,
+    reachable:    BTreeSet<u16>,
+    /// Nodes which vanished within the window, with the tick they vanished at.
+    vanished:     Vec<(u16, u16)>,
+    lost:         BTreeSet<u16>,
+}
+
+impl PartitionDetector {
+    pub fn new() -> Self {
+        Self {
+            window_ticks: DEFAULT_WINDOW_TICKS,
+            min_group:    DEFAULT_MIN_GROUP,
+            reachable:    BTreeSet::new(),
+            vanished:     Vec::new(),
+            lost:         BTreeSet::new(),
+        }
+    }
+
+    pub fn window_ticks(mut self, ticks: u16) -> Self {
+        self.window_ticks = ticks;
+        self
+    }
+
+    /// Set how many nodes have to vanish together to be considered a partition, at least two.
+    pub fn min_group(mut self, nodes: usize) -> Self {
+        self.min_group = nodes.max(2);
+        self
+    }
+
+    /// Record the set of nodes which are reachable at the given tick.
+    pub fn observe(&mut self, reachable: impl IntoIterator<Item = u16>, now_tick: u16) {
+        let reachable: BTreeSet<u16> = reachable.into_iter().collect();
+
+        for &id in self.reachable.difference(&reachable) {
+            self.vanished.push((id, now_tick));
+        }
+        self.vanished.retain(|&(id, vanished_at)| {
+            !reachable.contains(&id) && tick::elapsed(vanished_at, now_tick) <= self.window_ticks
+        });
+        self.lost.retain(|id| !reachable.contains(id));
+
+        if self.vanished.len() >= self.min_group {
+            self.lost.extend(self.vanished.drain(..).map(|(id, _)| id));
+        }
+
+        self.reachable = reachable;
+    }
+
+    /// Whether a group of nodes vanished together and did not come back yet.
+    pub fn is_partitioned(&self) -> bool {
+        !self.lost.is_empty()
+    }
+
+    /// The nodes cut off by partitions which are still unreachable, in ascending order.
+    pub fn lost_group(&self) -> Vec<u16> {
+        self.lost.iter().copied().collect()
+    }
+}
+
+impl Default for PartitionDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_vanishing_together_are_a_partition() {
+        let mut detector = PartitionDetector::new();
+        detector.observe([1, 2, 3, 4, 5], 0);
+        detector.observe([1, 2, 5], 1);
+        detector.observe([1], 3);
+
+        assert!(detector.is_partitioned());
+        assert_eq!(detector.lost_group(), [2, 3, 4, 5]);
+
+        detector.observe([1, 2, 3, 4], 20);
+        assert_eq!(detector.lost_group(), [5]);
+        detector.observe([1, 2, 3, 4, 5], 21);
+        assert!(!detector.is_partitioned());
+    }
+
+    #[test]
+    fn nodes_vanishing_individually_are_not() {
+        let mut detector = PartitionDetector::new().window_ticks(5);
+        detector.observe([1, 2, 3], u16::MAX - 20);
+        detector.observe([1, 2], u16::MAX - 19);
+        detector.observe([1], 10);
+        detector.observe([1, 4], 20);
+        detector.observe([4], 40);
+
+        assert!(!detector.is_partitioned());
+        assert!(detector.lost_group().is_empty());
+    }
+}
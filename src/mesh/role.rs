@@ -0,0 +1,120 @@
+//! Role-based acceptance rules, deciding whether a node should act on a received frame at all.
+//!
+//! A successfully decoded frame may still be meant for a different kind of node, like a `JoinRequest` which only a master
+//! can answer, or a `JoinResponse` claiming to come from a node which is not a master. [`Frame::check_for_role`] rejects
+//! such frames before the payload is handled.
+
+#[cfg(feature = "serde")]
+use serde_derive::{Serialize, Deserialize};
+
+use crate::frame::{Frame, FrameError, FramePayload, ControllerMessage};
+
+/// The responsibility of a node within the mesh.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NodeRole {
+    /// Assigns IDs to joining nodes, drives game rounds and bridges the mesh to connected GUI clients. Every master has an ID of zero.
+    Master,
+    /// A regular controller which joined the mesh of a master, or is still trying to.
+    Client,
+}
+
+impl Frame {
+    /// Check whether a node with the given role should act on this frame, returning `FrameError::NotForRole` otherwise.
+    ///
+    /// - `JoinRequest`s, `Ready`, `NotReady` and `BrightnessReport` answers and GUI client messages are only handled by masters.
+    /// - `JoinResponse`s, `Redirect`s and `SetUniverse`s are only handled by clients, and only if they were sent by a master.
+    /// - `DiscoverReply`s are only handled by clients, since masters never look for another master to join.
+    /// - `Batch`es are handled if every payload they carry is.
+    /// - Everything else, like `Discover` broadcasts and protocol messages, is handled by both roles.
+    pub fn check_for_role(&self, role: NodeRole) -> Result<(), FrameError> {
+        let accepted = match (&self.payload, role) {
+            (FramePayload::ControllerMessage(ControllerMessage::JoinRequest), NodeRole::Master) => true,
+            (FramePayload::ControllerMessage(ControllerMessage::JoinRequest), NodeRole::Client) => false,
+            (FramePayload::ControllerMessage(ControllerMessage::Ready | ControllerMessage::NotReady | ControllerMessage::BrightnessReport { .. }), role) => role == NodeRole::Master,
+            (FramePayload::ControllerMessage(ControllerMessage::JoinResponse { .. }), NodeRole::Master) => false,
+            // Only a master may assign IDs, so a response from anyone else is bogus.
+            (FramePayload::ControllerMessage(ControllerMessage::JoinResponse { .. }), NodeRole::Client) => self.sender_is_master(),
+            (FramePayload::ControllerMessage(ControllerMessage::Redirect { .. } | ControllerMessage::SetUniverse { .. }), NodeRole::Master) => false,
+            (FramePayload::ControllerMessage(ControllerMessage::Redirect { .. } | ControllerMessage::SetUniverse { .. }), NodeRole::Client) => self.sender_is_master(),
+            (FramePayload::ControllerMessage(ControllerMessage::DiscoverReply { .. }), role) => role == NodeRole::Client,
+            (FramePayload::ClientMessage(_), role) => role == NodeRole::Master,
+            (FramePayload::Batch(_), role) => self.clone().split_batch().iter().all(|part| part.check_for_role(role).is_ok()),
+            _ => true,
+        };
+
+        if accepted {
+            Ok(())
+        } else {
+            Err(FrameError::NotForRole(role))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{ClientMessage, ProtocolMessage, MASTER_ID};
+
+    #[test]
+    fn join_request_for_master_only() {
+        let frame = Frame::join_request(0);
+
+        assert_eq!(frame.check_for_role(NodeRole::Master), Ok(()));
+        assert_eq!(frame.check_for_role(NodeRole::Client), Err(FrameError::NotForRole(NodeRole::Client)));
+    }
+
+    #[test]
+    fn join_response_for_client_from_master_only() {
+        let from_master = Frame::join_response(0, 3);
+        let from_peer = Frame::join_response(0, 3).sender_id(7);
+
+        assert_eq!(from_master.check_for_role(NodeRole::Client), Ok(()));
+        assert_eq!(from_peer.check_for_role(NodeRole::Client), Err(FrameError::NotForRole(NodeRole::Client)));
+        assert_eq!(from_master.check_for_role(NodeRole::Master), Err(FrameError::NotForRole(NodeRole::Master)));
+    }
+
+    #[test]
+    fn redirect_for_client_from_master_only() {
+        for frame in [Frame::redirect(3, 1, 0), Frame::set_universe(3, 2)] {
+            assert_eq!(frame.check_for_role(NodeRole::Client), Ok(()));
+            assert_eq!(frame.clone().sender_id(7).check_for_role(NodeRole::Client), Err(FrameError::NotForRole(NodeRole::Client)));
+            assert_eq!(frame.check_for_role(NodeRole::Master), Err(FrameError::NotForRole(NodeRole::Master)));
+        }
+    }
+
+    #[test]
+    fn discovery_by_role() {
+        let discover = Frame::discover(0);
+        let reply = Frame::discover_reply(0, MASTER_ID, true, 0, 0);
+
+        assert_eq!(discover.check_for_role(NodeRole::Master), Ok(()));
+        assert_eq!(discover.check_for_role(NodeRole::Client), Ok(()));
+        assert_eq!(reply.check_for_role(NodeRole::Client), Ok(()));
+        assert_eq!(reply.check_for_role(NodeRole::Master), Err(FrameError::NotForRole(NodeRole::Master)));
+    }
+
+    #[test]
+    fn readiness_for_master_only() {
+        let frame = Frame::ready(0, true);
+
+        assert_eq!(frame.check_for_role(NodeRole::Master), Ok(()));
+        assert_eq!(frame.check_for_role(NodeRole::Client), Err(FrameError::NotForRole(NodeRole::Client)));
+    }
+
+    #[test]
+    fn client_message_for_master_only() {
+        let frame = Frame::new().client_message(ClientMessage::EndRound);
+
+        assert_eq!(frame.check_for_role(NodeRole::Master), Ok(()));
+        assert_eq!(frame.check_for_role(NodeRole::Client), Err(FrameError::NotForRole(NodeRole::Client)));
+    }
+
+    #[test]
+    fn protocol_message_for_both() {
+        let frame = Frame::new().protocol_message(ProtocolMessage::Tick(1));
+
+        assert_eq!(frame.check_for_role(NodeRole::Master), Ok(()));
+        assert_eq!(frame.check_for_role(NodeRole::Client), Ok(()));
+    }
+}
@@ -0,0 +1,300 @@
+//! Relaying of received frames through the mesh, with the forwarding rules exchangeable per deployment.
+//!
+//! The [`Router`] implements the mechanism every policy shares: frames are relayed at most once, never back from their
+//! own sender and only while their lifetime allows another hop and their deadline hasn't passed. Whether a frame is
+//! worth relaying at all is up to its [`ForwardPolicy`], which defaults to flooding.
+//!
+//! Besides remembering recent message IDs, the router records itself in the visited nodes of every relayed frame, which
+//! still catches loops once an ID was forgotten. When its memory is full, the router forgets
+//! [idempotent](Frame::is_idempotent) frames first, since relaying one of those again only costs airtime.
+//!
+//! Frames belonging to another universe are neither relayed nor delivered, and batches are split into their parts
+//! before they are [delivered](Router::deliver) to the node itself.
+//!
+//! Routing only ever looks at the header of a frame, so [encrypted](Frame::is_encrypted) frames are relayed like any
+//! other, with their payload passed on untouched for the target to decrypt.
+
+use std::collections::VecDeque;
+
+use crate::frame::{Frame, GLOBAL_UNIVERSE};
+use crate::mesh::topology::MeshTopology;
+
+/// How many message IDs a router remembers for detecting frames it already relayed.
+pub const DEFAULT_SEEN_CAPACITY: usize = 64;
+
+/// Decides whether this node relays a frame it received.
+///
+/// Policies should only decide based on the header, since the payload of encrypted frames can't be read by relays.
+pub trait ForwardPolicy {
+    fn should_forward(&self, frame: &Frame, topo: &MeshTopology, my_id: u16) -> bool;
+}
+
+/// Relay every frame, which reaches all nodes at the cost of the most airtime.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FloodPolicy;
+
+impl ForwardPolicy for FloodPolicy {
+    fn should_forward(&self, _frame: &Frame, _topo: &MeshTopology, _my_id: u16) -> bool {
+        true
+    }
+}
+
+/// Relay only along the spanning tree of the [`MeshTopology`]: broadcasts are relayed by every node with children,
+/// frames for a single target only by the nodes on the tree path between sender and target.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TreePolicy;
+
+impl ForwardPolicy for TreePolicy {
+    fn should_forward(&self, frame: &Frame, topo: &MeshTopology, my_id: u16) -> bool {
+        let Some(target_id) = frame.header.target_id else {
+            return !topo.children(my_id).is_empty();
+        };
+
+        let from_sender = topo.path_to_master(frame.header.sender_id);
+        let from_target = topo.path_to_master(target_id);
+
+        // The path between both runs up to their closest common ancestor and down again.
+        let Some(common) = from_sender.iter().position(|id| from_target.contains(id)) else {
+            return false;
+        };
+        let common_id = from_sender[common];
+        let down = from_target.iter().position(|&id| id == common_id).unwrap();
+
+        my_id != frame.header.sender_id && my_id != target_id
+            && (from_sender[..= common].contains(&my_id) || from_target[..= down].contains(&my_id))
+    }
+}
+
+/// Decides which received frames this node relays, and prepares them for the next hop.
+#[derive(Clone, Debug)]
+pub struct Router<P: ForwardPolicy = FloodPolicy> {
+    my_id:    u16,
+    universe: u8,
+    policy:   P,
+    /// The sender and message ID of recently considered frames, and whether they are idempotent.
+    seen:     VecDeque<(u16, String, bool)>,
+}
+
+impl Router {
+    /// A router flooding all frames.
+    pub fn new(my_id: u16) -> Self {
+        Self::with_policy(my_id, FloodPolicy)
+    }
+}
+
+impl<P: ForwardPolicy> Router<P> {
+    pub fn with_policy(my_id: u16, policy: P) -> Self {
+        Self { my_id, universe: GLOBAL_UNIVERSE, policy, seen: VecDeque::with_capacity(DEFAULT_SEEN_CAPACITY) }
+    }
+
+    /// Place the router in the given universe, so it drops frames of other universes except global ones.
+    pub fn universe(mut self, universe: u8) -> Self {
+        self.universe = universe;
+        self
+    }
+
+    /// Change the ID of this node, for example once it joined the mesh.
+    pub fn set_id(&mut self, my_id: u16) {
+        self.my_id = my_id;
+    }
+
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// The frames to act on for a received frame: the parts of a batch, the frame itself otherwise, and nothing if it
+    /// belongs to another universe. Whether the frame is addressed to this node is up to the caller.
+    pub fn deliver(&self, frame: Frame) -> Vec<Frame> {
+        if !frame.is_same_universe(self.universe) {
+            return Vec::new();
+        }
+
+        frame.split_batch()
+    }
+
+    /// The copy of the received frame to transmit next, with one hop less of lifetime, or `None` if it is not relayed.
+    ///
+    /// Every frame is only considered once, so later copies relayed by other nodes are dropped even if the first one
+    /// was not relayed. Frames [past their deadline](Frame::is_past_deadline) at `now_tick` are dropped regardless of
+    /// their lifetime.
+    pub fn route(&mut self, frame: &Frame, topo: &MeshTopology, now_tick: u16) -> Option<Frame> {
+        if frame.header.sender_id == self.my_id
+            || frame.header.target_id == Some(self.my_id)
+            || frame.has_visited(self.my_id)
+            || !frame.is_same_universe(self.universe)
+        {
+            return None;
+        }
+
+        if self.seen.iter().any(|(sender_id, message_id, _)| *sender_id == frame.header.sender_id && *message_id == frame.header.message_id) {
+            return None;
+        }
+        if self.seen.len() == DEFAULT_SEEN_CAPACITY {
+            let forgotten = self.seen.iter().position(|&(_, _, idempotent)| idempotent).unwrap_or(0);
+            self.seen.remove(forgotten);
+        }
+        self.seen.push_back((frame.header.sender_id, frame.header.message_id.clone(), frame.is_idempotent()));
+
+        if frame.header.lifetime <= 1 || frame.is_past_deadline(now_tick) || !self.policy.should_forward(frame, topo, self.my_id) {
+            return None;
+        }
+
+        let mut relayed = frame.clone();
+        relayed.header.lifetime -= 1;
+        relayed.mark_visited(self.my_id);
+        Some(relayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{FramePayload, ProtocolMessage, MASTER_ID};
+
+    /// ```text
+    /// 0 ── 1 ── 2 ── 3
+    ///      │
+    ///      4
+    /// ```
+    fn line() -> MeshTopology {
+        let mut topology = MeshTopology::new();
+        for (a, b) in [(0, 1), (1, 2), (2, 3), (1, 4)] {
+            topology.add_link(a, b);
+        }
+        topology
+    }
+
+    #[test]
+    fn policies_decide_differently() {
+        let topology = line();
+        let broadcast = Frame::tick(7).sender_id(MASTER_ID);
+        let to_leaf = Frame::join_request(3).sender_id(MASTER_ID).target_id(3);
+
+        // Node 4 is a leaf, so it is pointless for it to relay anything along the tree.
+        assert!(FloodPolicy.should_forward(&broadcast, &topology, 4));
+        assert!(!TreePolicy.should_forward(&broadcast, &topology, 4));
+        assert!(FloodPolicy.should_forward(&to_leaf, &topology, 4));
+        assert!(!TreePolicy.should_forward(&to_leaf, &topology, 4));
+
+        assert!(TreePolicy.should_forward(&broadcast, &topology, 2));
+        assert!(TreePolicy.should_forward(&to_leaf, &topology, 1));
+        assert!(TreePolicy.should_forward(&to_leaf, &topology, 2));
+        assert!(!TreePolicy.should_forward(&to_leaf, &topology, 3));
+    }
+
+    #[test]
+    fn tree_policy_between_clients() {
+        let topology = line();
+        let upwards = Frame::join_request(3).sender_id(4).target_id(2);
+
+        assert!(TreePolicy.should_forward(&upwards, &topology, 1));
+        assert!(!TreePolicy.should_forward(&upwards, &topology, MASTER_ID));
+        assert!(!TreePolicy.should_forward(&Frame::join_request(3).sender_id(4).target_id(9), &topology, 1));
+    }
+
+    #[test]
+    fn router_relays_once_with_less_lifetime() {
+        let topology = line();
+        let frame = Frame::tick(7).sender_id(MASTER_ID).lifetime(3);
+
+        let mut flooding = Router::new(4);
+        let relayed = flooding.route(&frame, &topology, 0).unwrap();
+        assert_eq!(relayed.header.lifetime, 2);
+        assert_eq!(relayed.header.visited, [4]);
+        assert_eq!(flooding.route(&frame, &topology, 0), None);
+        assert_eq!(flooding.route(&Frame::tick(7).sender_id(MASTER_ID).lifetime(1), &topology, 0), None);
+        assert_eq!(flooding.route(&Frame::tick(7).sender_id(4).lifetime(3), &topology, 0), None);
+
+        let mut tree = Router::with_policy(4, TreePolicy);
+        assert_eq!(tree.route(&frame, &topology, 0), None);
+        assert!(Router::with_policy(2, TreePolicy).route(&frame, &topology, 0).is_some());
+    }
+
+    #[test]
+    fn encrypted_frames_are_relayed_opaquely() {
+        let topology = line();
+        let secret = ProtocolMessage::Encrypted { key_id: 1, data: vec![0xA5; 12] };
+        let frame = Frame::control(secret.clone()).sender_id(MASTER_ID).target_id(3).lifetime(3);
+        assert!(frame.is_encrypted());
+        assert!(!Frame::join_request(0).is_encrypted());
+
+        let relayed = Router::with_policy(2, TreePolicy).route(&frame, &topology, 0).unwrap();
+        assert!(relayed.is_encrypted());
+        assert_eq!(relayed.as_protocol_message(), Some(&secret));
+        assert_eq!(relayed.as_controller_message(), None);
+        assert_eq!(relayed.to_client_message(), None);
+
+        // The target doesn't relay its own frames, it decrypts them.
+        assert_eq!(Router::new(3).route(&relayed, &topology, 0), None);
+    }
+
+    #[test]
+    fn frames_past_their_deadline_are_dropped() {
+        let topology = line();
+        let frame = Frame::tick(u16::MAX - 1).sender_id(MASTER_ID).lifetime(8).deadline(2);
+
+        assert!(Router::new(1).route(&frame, &topology, u16::MAX).is_some());
+        assert!(Router::new(1).route(&frame, &topology, 2).is_some());
+        assert_eq!(Router::new(1).route(&frame, &topology, 3), None);
+    }
+
+    #[test]
+    fn other_universes_are_neither_relayed_nor_delivered() {
+        let topology = line();
+        let mut router = Router::new(1).universe(1);
+
+        assert_eq!(router.route(&Frame::tick(5).sender_id(MASTER_ID).universe(2).lifetime(3), &topology, 0), None);
+        assert!(router.deliver(Frame::tick(5).universe(2)).is_empty());
+
+        assert!(router.route(&Frame::tick(6).sender_id(MASTER_ID).universe(GLOBAL_UNIVERSE).lifetime(3), &topology, 0).is_some());
+        assert!(router.route(&Frame::tick(7).sender_id(MASTER_ID).universe(1).lifetime(3), &topology, 0).is_some());
+        assert_eq!(router.deliver(Frame::tick(7).universe(1)).len(), 1);
+    }
+
+    #[test]
+    fn batches_are_split_on_delivery() {
+        let mut batch = Frame::tick(3);
+        batch.payload = FramePayload::Batch(vec![FramePayload::ProtocolMessage(ProtocolMessage::Tick(1)), FramePayload::ProtocolMessage(ProtocolMessage::Tick(2))]);
+
+        let parts = Router::new(1).deliver(batch.clone());
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| part.header == batch.header));
+        assert_eq!(parts[1].as_protocol_message(), Some(&ProtocolMessage::Tick(2)));
+    }
+
+    #[test]
+    fn idempotent_frames_are_forgotten_first() {
+        let topology = line();
+        let mut router = Router::new(4);
+        let join = Frame::join_request(0).sender_id(7).target_id(9).lifetime(3);
+        assert!(!join.is_idempotent());
+
+        assert!(router.route(&join, &topology, 0).is_some());
+        for tick in 0 .. DEFAULT_SEEN_CAPACITY as u16 * 2 {
+            assert!(router.route(&Frame::tick(tick).sender_id(MASTER_ID).lifetime(3), &topology, 0).is_some());
+        }
+
+        // The join request is still remembered, although far more frames followed it.
+        assert_eq!(router.route(&join, &topology, 0), None);
+    }
+
+    #[test]
+    fn visited_nodes_prevent_loops() {
+        // 0 ── 1 ── 2 ── 3 ── 1, so frames from the master come back around to node 1.
+        let mut topology = line();
+        topology.add_link(3, 1);
+
+        let mut one = Router::new(1);
+        let mut two = Router::new(2);
+        let mut three = Router::new(3);
+        let frame = Frame::tick(7).sender_id(MASTER_ID).lifetime(8);
+
+        let relayed = one.route(&frame, &topology, 0).unwrap();
+        let relayed = two.route(&relayed, &topology, 0).unwrap();
+        let relayed = three.route(&relayed, &topology, 0).unwrap();
+        assert_eq!(relayed.header.visited, [1, 2, 3]);
+
+        // Even after node 1 forgot the message ID, it doesn't relay the frame again.
+        assert_eq!(Router::new(1).route(&relayed, &topology, 0), None);
+    }
+}
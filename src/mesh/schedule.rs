@@ -0,0 +1,135 @@
+//! TDMA-style scheduling of frame emission on the synchronization tick, so nodes don't all transmit on the same tick.
+//!
+//! The repeating tick counter is divided into consecutive slots of `slot_ticks` ticks each, numbered from 0 to
+//! `slot_count - 1` before starting over. Every node is assigned one slot and only transmits while it is active.
+//! For the schedule to continue seamlessly when the tick counter wraps around, the period `slot_count * slot_ticks`
+//! should be a power of two.
+
+use crate::mesh::BackoffController;
+
+/// How node IDs are mapped to transmission slots.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SlotMapping {
+    /// Use the node ID modulo the slot count, so consecutive IDs get consecutive slots.
+    Modulo,
+    /// Always use the given slot, for deployments assigning slots by hand.
+    Fixed(u16),
+}
+
+/// Decides on which ticks a node may transmit.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Scheduler {
+    node_id:    u16,
+    slot_count: u16,
+    slot_ticks: u16,
+    mapping:    SlotMapping,
+}
+
+impl Scheduler {
+    /// A schedule with 16 slots of one tick each, with the slot chosen by node ID.
+    pub fn new(node_id: u16) -> Self {
+        Self {
+            node_id,
+            slot_count: 16,
+            slot_ticks: 1,
+            mapping: SlotMapping::Modulo,
+        }
+    }
+
+    /// Set the number of slots per period, at least one.
+    pub fn slot_count(mut self, slot_count: u16) -> Self {
+        self.slot_count = slot_count.max(1);
+        self
+    }
+
+    /// Set how many ticks a single slot lasts, at least one.
+    pub fn slot_ticks(mut self, slot_ticks: u16) -> Self {
+        self.slot_ticks = slot_ticks.max(1);
+        self
+    }
+
+    pub fn mapping(mut self, mapping: SlotMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// The slot this node transmits in.
+    pub fn slot(&self) -> u16 {
+        match self.mapping {
+            SlotMapping::Modulo => self.node_id % self.slot_count,
+            SlotMapping::Fixed(slot) => slot % self.slot_count,
+        }
+    }
+
+    /// The slot which is active on the given tick.
+    pub fn current_slot(&self, now_tick: u16) -> u16 {
+        (now_tick / self.slot_ticks) % self.slot_count
+    }
+
+    /// Whether this node may transmit on the given tick.
+    pub fn should_send(&self, now_tick: u16) -> bool {
+        self.current_slot(now_tick) == self.slot()
+    }
+
+    /// The first tick from `now_tick` on at which this node may transmit, additionally respecting the backoff of a
+    /// [`BackoffController`] if one is given.
+    pub fn next_send(&self, now_tick: u16, backoff: Option<&BackoffController>) -> u16 {
+        let from = backoff.map_or(now_tick, |backoff| backoff.next_send_allowed(now_tick));
+        let period = self.slot_count as u32 * self.slot_ticks as u32;
+
+        (0 .. period.min(u16::MAX as u32 + 1))
+            .map(|offset| from.wrapping_add(offset as u16))
+            .find(|&tick| self.should_send(tick))
+            .unwrap_or(from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn different_ids_do_not_overlap() {
+        let a = Scheduler::new(1).slot_count(8).slot_ticks(4);
+        let b = Scheduler::new(2).slot_count(8).slot_ticks(4);
+
+        assert_ne!(a.slot(), b.slot());
+        assert!((0 ..= u16::MAX).all(|tick| !(a.should_send(tick) && b.should_send(tick))));
+        assert_eq!((0 ..= u16::MAX).filter(|&tick| a.should_send(tick)).count(), 65536 / 8);
+    }
+
+    #[test]
+    fn schedule_repeats_across_tick_wrap() {
+        let scheduler = Scheduler::new(3).slot_count(4).slot_ticks(2);
+        let period = 4 * 2;
+
+        for tick in u16::MAX - 20 ..= u16::MAX {
+            assert_eq!(scheduler.should_send(tick), scheduler.should_send(tick.wrapping_add(period)));
+        }
+    }
+
+    #[test]
+    fn fixed_mapping() {
+        let scheduler = Scheduler::new(1).slot_count(4).mapping(SlotMapping::Fixed(3));
+
+        assert_eq!(scheduler.slot(), 3);
+        assert!(scheduler.should_send(7));
+        assert!(!scheduler.should_send(1));
+    }
+
+    #[test]
+    fn next_send_respects_slot_and_backoff() {
+        let scheduler = Scheduler::new(2).slot_count(4);
+        assert_eq!(scheduler.next_send(0, None), 2);
+        assert_eq!(scheduler.next_send(2, None), 2);
+        assert_eq!(scheduler.next_send(u16::MAX, None), 2);
+
+        let mut backoff = BackoffController::new(2).min_window(100);
+        backoff.on_collision();
+        let allowed = backoff.next_send_allowed(10);
+        let next = scheduler.next_send(10, Some(&backoff));
+
+        assert!(scheduler.should_send(next));
+        assert!(next >= allowed && next - allowed < 4);
+    }
+}
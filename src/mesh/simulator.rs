@@ -0,0 +1,432 @@
+//! A deterministic simulation of several nodes sharing a lossy radio medium, available with the `sim` feature.
+//!
+//! Every [`VirtualNode`] implements the node behaviour built from the other primitives of this module: masters assign
+//! IDs to joining clients, clients join the mesh, and frames requiring an acknowledgement are tracked by an
+//! [`AckManager`] and retried until they are acknowledged. Every node relays and delivers frames through a flooding
+//! [`Router`]. The [`Medium`] broadcasts every frame to all other nodes in range after a delay, dropping frames with a
+//! configurable probability. Loss is drawn from a seeded generator, so a simulation behaves the same on every run.
+//!
+//! ```rust
+//! use ledswarm_protocol::mesh::simulator::{Medium, Simulator, VirtualNode};
+//!
+//! let mut sim = Simulator::new(Medium::new().loss(0.2));
+//! let master = sim.add_node(VirtualNode::master());
+//! let client = sim.add_node(VirtualNode::client());
+//!
+//! sim.run(0 .. 500);
+//! assert!(sim.node(client).has_received_join_response());
+//! assert_eq!(sim.node(client).id(), Some(1));
+//! # let _ = master;
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use crate::frame::{ControllerMessage, Frame, ProtocolMessage, MASTER_ID};
+use crate::mesh::{tick, AckAction, AckManager, MeshTopology, NodeRole, Router};
+
+/// The radio medium shared by all nodes of a simulation.
+#[derive(Clone, Debug)]
+pub struct Medium {
+    loss:         f32,
+    delay_ticks:  u16,
+    rng:          u64,
+    in_flight:    Vec<InFlight>,
+    /// Pairs of node handles, the lower one first, which cannot hear each other.
+    out_of_range: BTreeSet<(usize, usize)>,
+}
+
+#[derive(Clone, Debug)]
+struct InFlight {
+    from:    usize,
+    sent_at: u16,
+    frame:   Frame,
+}
+
+impl Medium {
+    /// A lossless medium delivering every frame on the tick after it was sent.
+    pub fn new() -> Self {
+        Self {
+            loss:         0.0,
+            delay_ticks:  1,
+            rng:          0x853C_49E6_748F_EA9B,
+            in_flight:    Vec::new(),
+            out_of_range: BTreeSet::new(),
+        }
+    }
+
+    /// Set the probability between 0.0 and 1.0 that a frame is lost, independently for every receiving node.
+    pub fn loss(mut self, probability: f32) -> Self {
+        self.loss = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how many ticks a frame takes to arrive, at least one.
+    pub fn delay(mut self, ticks: u16) -> Self {
+        self.delay_ticks = ticks.max(1);
+        self
+    }
+
+    /// Seed the generator deciding which frames are lost, to run the same scenario with different losses.
+    pub fn seed(mut self, seed: u64) -> Self {
+        // Xorshift must not be seeded with zero.
+        self.rng = seed | 1;
+        self
+    }
+
+    /// The number of frames which were sent but not delivered yet.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    fn send(&mut self, from: usize, frame: Frame, now_tick: u16) {
+        self.in_flight.push(InFlight { from, sent_at: now_tick, frame });
+    }
+
+    /// Remove the frames which arrive at the given tick.
+    fn arriving(&mut self, now_tick: u16) -> Vec<InFlight> {
+        let delay_ticks = self.delay_ticks;
+        let (arriving, in_flight) = self.in_flight
+            .drain(..)
+            .partition(|sent| tick::elapsed(sent.sent_at, now_tick) >= delay_ticks);
+
+        self.in_flight = in_flight;
+        arriving
+    }
+
+    fn in_range(&self, a: usize, b: usize) -> bool {
+        !self.out_of_range.contains(&(a.min(b), a.max(b)))
+    }
+
+    fn is_lost(&mut self) -> bool {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        ((self.rng >> 40) as f32 / (1u64 << 24) as f32) < self.loss
+    }
+}
+
+impl Default for Medium {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A simulated node, either a master or a client joining the mesh.
+#[derive(Clone, Debug)]
+pub struct VirtualNode {
+    role:        NodeRole,
+    id:          Option<u16>,
+    acks:        AckManager,
+    router:      Router,
+    received:    Vec<Frame>,
+    outbox:      Vec<Frame>,
+    /// Clients: the message ID of their join request, answered by the master's join response.
+    join_id:     Option<String>,
+    /// Masters: the ID assigned to each join request, so retried requests get the same ID.
+    assignments: BTreeMap<String, u16>,
+    next_id:     u16,
+}
+
+impl VirtualNode {
+    /// A master node, which assigns the IDs 1, 2, 3, ... to joining clients.
+    pub fn master() -> Self {
+        Self::new(NodeRole::Master, Some(MASTER_ID))
+    }
+
+    /// A client node without an ID, which sends a join request on the first tick of the simulation.
+    pub fn client() -> Self {
+        Self::new(NodeRole::Client, None)
+    }
+
+    fn new(role: NodeRole, id: Option<u16>) -> Self {
+        Self {
+            role,
+            id,
+            acks:        AckManager::new().timeout_ticks(10),
+            router:      Router::new(id.unwrap_or(u16::MAX)),
+            received:    Vec::new(),
+            outbox:      Vec::new(),
+            join_id:     None,
+            assignments: BTreeMap::new(),
+            next_id:     1,
+        }
+    }
+
+    /// Place the node in the given universe, so it drops frames of other universes except global ones.
+    pub fn universe(mut self, universe: u8) -> Self {
+        self.router = self.router.universe(universe);
+        self
+    }
+
+    /// Change the acknowledgement timeout used by this node, 10 ticks by default.
+    pub fn ack_timeout_ticks(mut self, ticks: u16) -> Self {
+        self.acks = self.acks.timeout_ticks(ticks);
+        self
+    }
+
+    pub fn role(&self) -> NodeRole {
+        self.role
+    }
+
+    /// The ID of the node, or `None` for clients which did not join the mesh yet.
+    pub fn id(&self) -> Option<u16> {
+        self.id
+    }
+
+    /// Every frame which was delivered to this node and addressed to it, in order of arrival.
+    pub fn received(&self) -> &[Frame] {
+        &self.received
+    }
+
+    /// Whether any received frame matches the predicate.
+    pub fn has_received(&self, predicate: impl Fn(&Frame) -> bool) -> bool {
+        self.received.iter().any(predicate)
+    }
+
+    /// Whether the node received a `JoinResponse` from its master, the frame counterpart of a legacy `Welcome`.
+    pub fn has_received_join_response(&self) -> bool {
+        self.has_received(|frame| matches!(frame.as_controller_message(), Some(ControllerMessage::JoinResponse { .. })))
+    }
+
+    fn send(&mut self, frame: Frame, now_tick: u16) {
+        self.acks.track(frame.clone(), now_tick);
+        self.outbox.push(frame);
+    }
+
+    /// Whether a frame on the medium is meant for this node.
+    fn is_addressed_to(&self, frame: &Frame) -> bool {
+        let answers_join = self.join_id.is_some() && frame.header.acked_message_id == self.join_id;
+
+        match frame.header.target_id {
+            None => true,
+            target => target == self.id || answers_join,
+        }
+    }
+
+    fn on_tick(&mut self, now_tick: u16) {
+        if self.role == NodeRole::Client && self.id.is_none() && self.join_id.is_none() {
+            let request = Frame::join_request(now_tick);
+            self.join_id = Some(request.header.message_id.clone());
+            self.send(request, now_tick);
+        }
+
+        for action in self.acks.poll(now_tick) {
+            if let AckAction::Retry(frame) = action {
+                self.outbox.push(frame.clone_with_tick(now_tick));
+            }
+        }
+    }
+
+    fn on_frame(&mut self, frame: Frame, now_tick: u16) {
+        // The simulated nodes flood, which needs no knowledge of the links between them.
+        if let Some(relayed) = self.router.route(&frame, &MeshTopology::new(), now_tick) {
+            self.outbox.push(relayed);
+        }
+
+        for part in self.router.deliver(frame) {
+            self.on_delivered(part, now_tick);
+        }
+    }
+
+    fn on_delivered(&mut self, frame: Frame, now_tick: u16) {
+        if !self.is_addressed_to(&frame) || frame.check_for_role(self.role).is_err() {
+            return;
+        }
+
+        self.acks.on_frame(&frame);
+
+        match frame.as_controller_message() {
+            Some(ControllerMessage::JoinRequest) => {
+                let message_id = frame.header.message_id.clone();
+                let assigned_id = match self.assignments.get(&message_id) {
+                    Some(&id) => id,
+                    None => {
+                        let id = self.next_id;
+                        self.next_id += 1;
+                        self.assignments.insert(message_id.clone(), id);
+                        id
+                    },
+                };
+
+                // Answering acknowledges the request, so no separate acknowledgement is needed.
+                self.send(Frame::join_response(now_tick, assigned_id).also_acks(message_id), now_tick);
+            },
+            Some(ControllerMessage::JoinResponse { assigned_id }) => {
+                self.id = Some(*assigned_id);
+                self.router.set_id(*assigned_id);
+                self.acknowledge(&frame, now_tick);
+            },
+            _ => self.acknowledge(&frame, now_tick),
+        }
+
+        self.received.push(frame);
+    }
+
+    fn acknowledge(&mut self, frame: &Frame, now_tick: u16) {
+        if !frame.header.requires_acknowledgement {
+            return;
+        }
+
+        let ack = Frame::new()
+            .protocol_message(ProtocolMessage::Acknowledged { message_id: frame.header.message_id.clone() })
+            .sender_id(self.id.unwrap_or(u16::MAX))
+            .target_id(frame.header.sender_id)
+            .current_tick(now_tick);
+        self.outbox.push(ack);
+    }
+}
+
+/// Drives a set of virtual nodes connected by a medium, one tick at a time.
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    medium: Medium,
+    nodes:  Vec<VirtualNode>,
+}
+
+impl Simulator {
+    pub fn new(medium: Medium) -> Self {
+        Self { medium, nodes: Vec::new() }
+    }
+
+    /// Add a node to the simulation, returning the handle to look it up with, since client IDs change when they join.
+    pub fn add_node(&mut self, node: VirtualNode) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// The node with the given handle. Panics if the handle was not returned by [`Simulator::add_node`].
+    pub fn node(&self, handle: usize) -> &VirtualNode {
+        &self.nodes[handle]
+    }
+
+    pub fn nodes(&self) -> &[VirtualNode] {
+        &self.nodes
+    }
+
+    pub fn medium(&self) -> &Medium {
+        &self.medium
+    }
+
+    /// Place the two nodes out of each other's range, so frames between them have to be relayed by other nodes.
+    pub fn out_of_range(&mut self, a: usize, b: usize) {
+        self.medium.out_of_range.insert((a.min(b), a.max(b)));
+    }
+
+    /// Simulate a single tick: deliver the frames arriving on it, then let every node act and send its frames.
+    pub fn step(&mut self, now_tick: u16) {
+        for sent in self.medium.arriving(now_tick) {
+            for receiver in 0 .. self.nodes.len() {
+                if receiver != sent.from && self.medium.in_range(sent.from, receiver) && !self.medium.is_lost() {
+                    self.nodes[receiver].on_frame(sent.frame.clone(), now_tick);
+                }
+            }
+        }
+
+        for (handle, node) in self.nodes.iter_mut().enumerate() {
+            node.on_tick(now_tick);
+            for frame in node.outbox.drain(..) {
+                self.medium.send(handle, frame, now_tick);
+            }
+        }
+    }
+
+    /// Simulate every tick of the range in order.
+    pub fn run(&mut self, ticks: Range<u16>) {
+        for now_tick in ticks {
+            self.step(now_tick);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::GLOBAL_UNIVERSE;
+
+    fn join_handshake(medium: Medium) -> Simulator {
+        let mut sim = Simulator::new(medium);
+        sim.add_node(VirtualNode::master());
+        sim.add_node(VirtualNode::client());
+        sim.add_node(VirtualNode::client());
+
+        sim.run(0 .. 1000);
+        sim
+    }
+
+    #[test]
+    fn three_node_join_handshake() {
+        let sim = join_handshake(Medium::new());
+
+        let mut ids: Vec<_> = sim.nodes()[1 ..].iter().map(|node| node.id().unwrap()).collect();
+        ids.sort();
+        assert_eq!(ids, [1, 2]);
+
+        assert!(sim.node(1).has_received_join_response());
+        assert!(sim.node(2).has_received_join_response());
+        assert!(sim.nodes().iter().all(|node| node.acks.pending_len() == 0));
+        assert_eq!(sim.medium().in_flight(), 0);
+    }
+
+    #[test]
+    fn join_handshake_survives_loss() {
+        for seed in 0 .. 10 {
+            let sim = join_handshake(Medium::new().loss(0.3).delay(2).seed(seed));
+
+            let mut ids: Vec<_> = sim.nodes()[1 ..].iter().filter_map(VirtualNode::id).collect();
+            ids.sort();
+            assert_eq!(ids, [1, 2], "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn clients_out_of_range_join_through_a_relay() {
+        let mut sim = Simulator::new(Medium::new());
+        let master = sim.add_node(VirtualNode::master());
+        let relay = sim.add_node(VirtualNode::client());
+        sim.run(0 .. 50);
+        assert_eq!(sim.node(relay).id(), Some(1));
+
+        let far = sim.add_node(VirtualNode::client());
+        sim.out_of_range(master, far);
+        sim.run(50 .. 200);
+
+        assert!(sim.node(far).has_received_join_response());
+        assert_eq!(sim.node(far).id(), Some(2));
+    }
+
+    #[test]
+    fn simulation_is_deterministic() {
+        let run = || {
+            let sim = join_handshake(Medium::new().loss(0.5).seed(3));
+            sim.nodes().iter().map(|node| node.received().len()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn nodes_drop_other_universes() {
+        let mut node = VirtualNode::master().universe(1);
+
+        node.on_frame(Frame::tick(5).universe(2), 5);
+        assert!(node.received().is_empty());
+
+        node.on_frame(Frame::tick(6).universe(GLOBAL_UNIVERSE), 6);
+        node.on_frame(Frame::tick(7).universe(1), 7);
+        assert_eq!(node.received().len(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn batches_are_split_on_receive() {
+        use crate::frame::FramePayload;
+
+        let mut node = VirtualNode::master();
+        let ticks = [Frame::tick(1), Frame::tick(2)];
+
+        node.on_frame(Frame::coalesce(&ticks).unwrap(), 3);
+        assert_eq!(node.received().len(), 2);
+        assert!(node.received().iter().all(|frame| matches!(frame.payload, FramePayload::ProtocolMessage(_))));
+    }
+}
@@ -0,0 +1,104 @@
+//! Statistics a node gathers about its links, for choosing routes and spotting flaky neighbors.
+//!
+//! The quality of a link is the share of acknowledged frames sent to the neighbor, as an exponentially weighted
+//! moving average, so recent outcomes count more than old ones and a link recovers once it works again. Links start
+//! out with a quality of one, since a neighbor that was just heard from is assumed to be reachable.
+
+use std::collections::BTreeMap;
+
+/// The weight of every new outcome in the moving average by default. With a quarter, three consecutive timeouts take
+/// a perfect link below half its quality.
+pub const DEFAULT_QUALITY_WEIGHT: f32 = 0.25;
+
+/// The exponentially weighted success ratio of frames sent to each neighbor.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LinkQuality {
+    weight:    f32,
+    neighbors: BTreeMap<u16, f32>,
+}
+
+impl LinkQuality {
+    pub fn new() -> Self {
+        Self { weight: DEFAULT_QUALITY_WEIGHT, neighbors: BTreeMap::new() }
+    }
+
+    /// Set the weight of every new outcome in the moving average, clamped to `0.0 ..= 1.0`. A weight of one only
+    /// considers the latest outcome.
+    pub fn weight(mut self, weight: f32) -> Self {
+        self.weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Record that a frame sent to the neighbor was acknowledged.
+    pub fn on_ack(&mut self, neighbor_id: u16) {
+        self.record(neighbor_id, 1.0);
+    }
+
+    /// Record that the acknowledgement of a frame sent to the neighbor timed out.
+    pub fn on_timeout(&mut self, neighbor_id: u16) {
+        self.record(neighbor_id, 0.0);
+    }
+
+    /// The quality of the link to the neighbor in `0.0 ..= 1.0`. Neighbors without any recorded outcome have a quality
+    /// of one.
+    pub fn quality(&self, neighbor_id: u16) -> f32 {
+        self.neighbors.get(&neighbor_id).copied().unwrap_or(1.0)
+    }
+
+    /// The neighbors with a recorded outcome together with the quality of their link, in ascending order of their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, f32)> + '_ {
+        self.neighbors.iter().map(|(&id, &quality)| (id, quality))
+    }
+
+    /// Forget the statistics of a neighbor, for example once it left the mesh.
+    pub fn forget(&mut self, neighbor_id: u16) {
+        self.neighbors.remove(&neighbor_id);
+    }
+
+    fn record(&mut self, neighbor_id: u16, outcome: f32) {
+        let quality = self.neighbors.entry(neighbor_id).or_insert(1.0);
+        *quality += self.weight * (outcome - *quality);
+    }
+}
+
+impl Default for LinkQuality {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_lower_and_acks_raise_quality() {
+        let mut links = LinkQuality::new();
+        assert_eq!(links.quality(4), 1.0);
+
+        links.on_timeout(4);
+        let after_one = links.quality(4);
+        links.on_timeout(4);
+        links.on_timeout(4);
+        assert!(links.quality(4) < after_one);
+        assert!(links.quality(4) < 0.5);
+
+        let low = links.quality(4);
+        links.on_ack(4);
+        assert!(links.quality(4) > low);
+        assert!((0.0 ..= 1.0).contains(&links.quality(4)));
+
+        assert_eq!(links.iter().collect::<Vec<_>>(), [(4, links.quality(4))]);
+        links.forget(4);
+        assert_eq!(links.quality(4), 1.0);
+    }
+
+    #[test]
+    fn weight_of_one_only_keeps_the_latest_outcome() {
+        let mut links = LinkQuality::new().weight(2.0);
+        links.on_timeout(1);
+        assert_eq!(links.quality(1), 0.0);
+        links.on_ack(1);
+        assert_eq!(links.quality(1), 1.0);
+    }
+}
@@ -0,0 +1,34 @@
+//! Wraparound-safe arithmetic on the repeating 16-bit synchronization tick.
+//!
+//! The tick counter wraps around from 65535 back to 0, so comparing two ticks with plain integer operators breaks down
+//! whenever the counter overflowed in between. These helpers interpret the distance between two ticks modulo 2^16.
+
+/// The number of ticks which have passed from `since` until `now`, assuming `now` is not more than one full cycle later.
+pub fn elapsed(since: u16, now: u16) -> u16 {
+    now.wrapping_sub(since)
+}
+
+/// The signed distance from `from` to `to`, negative if `to` lies before `from`, assuming the two ticks are less than
+/// half a cycle apart.
+pub fn offset(from: u16, to: u16) -> i16 {
+    to.wrapping_sub(from) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elapsed_across_wrap() {
+        assert_eq!(elapsed(10, 15), 5);
+        assert_eq!(elapsed(u16::MAX - 1, 3), 5);
+    }
+
+    #[test]
+    fn offset_across_wrap() {
+        assert_eq!(offset(10, 15), 5);
+        assert_eq!(offset(15, 10), -5);
+        assert_eq!(offset(u16::MAX - 1, 3), 5);
+        assert_eq!(offset(3, u16::MAX - 1), -5);
+    }
+}
@@ -0,0 +1,225 @@
+//! The links between nodes of the mesh as far as a node knows them, for deciding how frames are forwarded.
+//!
+//! Links are undirected, since a UWB link that works in one direction practically always works in the other. On top of
+//! the links, the topology derives a spanning tree rooted at the master, which is rebuilt on every change.
+//!
+//! Links may also carry the distance ranged between both nodes, from which [`MeshTopology::cost`] estimates how
+//! expensive reaching a node is. Every hop costs [`HOP_COST`] for the airtime of relaying the frame, plus the distance
+//! of the link in centimetres, since longer links lose more frames.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::frame::{Frame, MASTER_ID};
+
+/// The cost of every hop on a route regardless of its distance, equal to that of one metre of distance.
+pub const HOP_COST: u32 = 100;
+
+/// Known links between nodes and the spanning tree rooted at the master.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MeshTopology {
+    links:     BTreeMap<u16, BTreeSet<u16>>,
+    parents:   BTreeMap<u16, u16>,
+    /// The ranged distance of links in centimetres, keyed by the lower ID first.
+    distances: BTreeMap<(u16, u16), u32>,
+}
+
+impl MeshTopology {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the two nodes can hear each other.
+    pub fn add_link(&mut self, a: u16, b: u16) {
+        if a == b {
+            return;
+        }
+
+        self.links.entry(a).or_default().insert(b);
+        self.links.entry(b).or_default().insert(a);
+        self.rebuild_tree();
+    }
+
+    /// Forget the node and all its links, for example once it is no longer alive.
+    pub fn remove_node(&mut self, id: u16) {
+        if let Some(neighbors) = self.links.remove(&id) {
+            for neighbor in neighbors {
+                if let Some(links) = self.links.get_mut(&neighbor) {
+                    links.remove(&id);
+                }
+                self.distances.remove(&link_key(id, neighbor));
+            }
+            self.rebuild_tree();
+        }
+    }
+
+    /// Record the link between two nodes together with the distance ranged between them, for example with
+    /// [`TwoWayRanging::distance_m`](crate::frame::ranging::TwoWayRanging::distance_m).
+    pub fn set_distance(&mut self, a: u16, b: u16, distance_m: f64) {
+        if a == b {
+            return;
+        }
+
+        self.distances.insert(link_key(a, b), (distance_m.max(0.0) * 100.0).round() as u32);
+        self.add_link(a, b);
+    }
+
+    /// The ranged distance of the link between two nodes in metres, `None` if no distance is known for it.
+    pub fn distance(&self, a: u16, b: u16) -> Option<f64> {
+        self.distances.get(&link_key(a, b)).map(|&centimetres| centimetres as f64 / 100.0)
+    }
+
+    /// The cost of the cheapest route between two nodes, see the [module documentation](self), or `None` if there is
+    /// no route between them. Links without a known distance only cost [`HOP_COST`].
+    pub fn cost(&self, from: u16, to: u16) -> Option<u32> {
+        let mut costs = BTreeMap::from([(from, 0)]);
+        let mut queue = BTreeSet::from([(0, from)]);
+
+        // Dijkstra's algorithm, with the ordered set as the priority queue.
+        while let Some((cost, id)) = queue.pop_first() {
+            if id == to {
+                return Some(cost);
+            }
+
+            for &neighbor in self.links.get(&id).into_iter().flatten() {
+                let link_cost = HOP_COST.saturating_add(self.distances.get(&link_key(id, neighbor)).copied().unwrap_or(0));
+                let neighbor_cost = cost.saturating_add(link_cost);
+                if costs.get(&neighbor).is_none_or(|&known| neighbor_cost < known) {
+                    if let Some(known) = costs.insert(neighbor, neighbor_cost) {
+                        queue.remove(&(known, neighbor));
+                    }
+                    queue.insert((neighbor_cost, neighbor));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The direct neighbors of the node, in ascending order.
+    pub fn neighbors(&self, id: u16) -> Vec<u16> {
+        self.links.get(&id).map_or_else(Vec::new, |links| links.iter().copied().collect())
+    }
+
+    /// The next node on the way from the node to the master in the spanning tree. `None` for the master itself and
+    /// for nodes without a path to it.
+    pub fn parent(&self, id: u16) -> Option<u16> {
+        self.parents.get(&id).copied()
+    }
+
+    /// The nodes whose parent is the given node, in ascending order.
+    pub fn children(&self, id: u16) -> Vec<u16> {
+        self.parents.iter().filter(|(_, &parent)| parent == id).map(|(&child, _)| child).collect()
+    }
+
+    /// The node followed by its ancestors up to and including the master, or an empty path if the node has no path to
+    /// the master.
+    pub fn path_to_master(&self, id: u16) -> Vec<u16> {
+        if id != MASTER_ID && !self.parents.contains_key(&id) {
+            return Vec::new();
+        }
+
+        let mut path = vec![id];
+        while let Some(parent) = self.parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+        path
+    }
+
+    /// Breadth-first from the master, so every node hangs off a neighbor with the fewest hops to the master. Ties are
+    /// broken by the lower ID, which keeps the tree identical on all nodes knowing the same links.
+    fn rebuild_tree(&mut self) {
+        self.parents.clear();
+
+        let mut visited = BTreeSet::from([MASTER_ID]);
+        let mut queue = VecDeque::from([MASTER_ID]);
+
+        while let Some(id) = queue.pop_front() {
+            for &neighbor in self.links.get(&id).into_iter().flatten() {
+                if visited.insert(neighbor) {
+                    self.parents.insert(neighbor, id);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+}
+
+fn link_key(a: u16, b: u16) -> (u16, u16) {
+    (a.min(b), a.max(b))
+}
+
+impl Frame {
+    /// The estimated cost for this node to deliver the frame to its target, see [`MeshTopology::cost`]. `None` for
+    /// broadcasts, which have no single target, and for targets this node has no route to.
+    pub fn route_cost(&self, topo: &MeshTopology, my_id: u16) -> Option<u32> {
+        topo.cost(my_id, self.header.target_id?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ```text
+    /// 0 ── 1 ── 3
+    /// │    │
+    /// 2 ───┘    4 ── 5
+    /// ```
+    fn sample() -> MeshTopology {
+        let mut topology = MeshTopology::new();
+        for (a, b) in [(0, 1), (0, 2), (1, 2), (1, 3), (4, 5)] {
+            topology.add_link(a, b);
+        }
+        topology
+    }
+
+    #[test]
+    fn spanning_tree_from_master() {
+        let topology = sample();
+
+        assert_eq!(topology.neighbors(1), [0, 2, 3]);
+        assert_eq!(topology.parent(0), None);
+        assert_eq!(topology.parent(2), Some(0));
+        assert_eq!(topology.parent(3), Some(1));
+        assert_eq!(topology.children(0), [1, 2]);
+        assert_eq!(topology.path_to_master(3), [3, 1, 0]);
+        assert!(topology.path_to_master(5).is_empty());
+    }
+
+    #[test]
+    fn removing_a_node_rebuilds_the_tree() {
+        let mut topology = sample();
+        topology.remove_node(0);
+
+        assert_eq!(topology.neighbors(1), [2, 3]);
+        assert_eq!(topology.parent(3), None);
+        assert!(topology.path_to_master(3).is_empty());
+    }
+
+    #[test]
+    fn route_cost_prefers_short_links() {
+        // Two routes from 1 to 4: one hop over a 30 m link, or two hops over 5 m links through 2.
+        let mut topology = MeshTopology::new();
+        topology.set_distance(1, 4, 30.0);
+        topology.set_distance(1, 2, 5.0);
+        topology.set_distance(2, 4, 5.0);
+        topology.add_link(4, 5);
+
+        assert_eq!(topology.distance(4, 1), Some(30.0));
+        assert_eq!(topology.distance(4, 5), None);
+        assert_eq!(topology.cost(1, 4), Some(2 * HOP_COST + 1000));
+        assert_eq!(topology.cost(1, 5), Some(3 * HOP_COST + 1000));
+        assert_eq!(topology.cost(1, 1), Some(0));
+
+        let frame = Frame::join_request(0).target_id(4);
+        assert_eq!(frame.route_cost(&topology, 1), topology.cost(1, 4));
+        assert_eq!(frame.clone().target_id(9).route_cost(&topology, 1), None);
+        assert_eq!(Frame::tick(0).route_cost(&topology, 1), None);
+
+        // Once the short links get long, the direct link wins.
+        topology.set_distance(2, 4, 40.0);
+        assert_eq!(topology.cost(1, 4), Some(HOP_COST + 3000));
+        topology.remove_node(4);
+        assert_eq!(topology.distance(1, 4), None);
+    }
+}
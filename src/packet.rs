@@ -1,16 +1,39 @@
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "serde")]
 use serde_derive::{Serialize, Deserialize};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+use crate::frame::{ControllerMessage, FrameError, ProtocolMessage};
+#[cfg(feature = "serde")]
+use bincode::Options;
+
+use crate::wire::{self, WireError};
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UwbPacketError {
     /// An error occurred while serializing or deserializing the packet.
     SerializeError,
     /// The magic string "LEDswarm" was not found at the start of the byte buffer, so the received datagram is not a valid LEDswarm UWB packet.
     NoMagicString(String),
+    /// The byte buffer is too short to contain a packet.
+    TooShort { expected: usize, actual: usize },
+}
+
+impl From<WireError> for UwbPacketError {
+    fn from(error: WireError) -> Self {
+        match error {
+            WireError::TooShort { expected, actual } => Self::TooShort { expected, actual },
+            WireError::NoMagicString(found) => Self::NoMagicString(found),
+        }
+    }
 }
 
 /// A data packet sent between controllers in the UWB mesh.
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UwbPacket {
     //pub uuid: String,
     pub sender_id: u16,
@@ -22,38 +45,51 @@ pub struct UwbPacket {
     pub lifetime: u8,
 }
 
-impl From<UwbPacket> for Vec<u8> {
-    fn from(packet: UwbPacket) -> Vec<u8> {
-        let mut buffer = Vec::new();
+impl UwbPacket {
+    /// Whether the buffer starts with the magic string, see [`Frame::verify_magic_only`](crate::frame::Frame::verify_magic_only).
+    pub fn verify_magic_only(bytes: &[u8]) -> bool {
+        wire::has_magic(bytes)
+    }
 
-        buffer.extend_from_slice("LEDswarm".as_bytes());
-        buffer.append(&mut bincode::serialize(&packet).unwrap());
-        buffer.append(&mut packet.ranging_bytes.to_vec());
+    /// Encode the packet for the wire, failing with `UwbPacketError::SerializeError` if its body exceeds
+    /// [`MAX_FRAME_SIZE`](crate::frame::MAX_FRAME_SIZE), which receivers would reject anyway.
+    #[cfg(feature = "serde")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, UwbPacketError> {
+        let body = wire::bincode_options().serialize(self).map_err(|_e| UwbPacketError::SerializeError)?;
+        Ok(wire::frame_bytes(wire::MAGIC, &body, self.ranging_bytes))
+    }
+}
 
-        buffer
+#[cfg(feature = "serde")]
+impl TryFrom<UwbPacket> for Vec<u8> {
+    type Error = UwbPacketError;
+
+    fn try_from(packet: UwbPacket) -> Result<Vec<u8>, UwbPacketError> {
+        packet.to_bytes()
     }
 }
 
+#[cfg(feature = "serde")]
 impl TryFrom<Vec<u8>> for UwbPacket {
     type Error = UwbPacketError;
 
     fn try_from(vec: Vec<u8>) -> Result<Self, UwbPacketError> {
-        if core::str::from_utf8(&vec[0 .. 8]).unwrap() == "LEDswarm" {
-            match bincode::deserialize::<Self>(&vec["LEDswarm".len() .. vec.len() - 4]) {
-                Ok(mut packet) => {
-                    // Extract the last four bytes, which are ranging data, and put them into the message, so they can be restored if the message is serialized again.
-                    packet.ranging_bytes = [vec[vec.len() - 4], vec[vec.len() - 3], vec[vec.len() - 2], vec[vec.len() - 1]];
-                    Ok(packet)
-                },
-                Err(_e) => Err(UwbPacketError::SerializeError),
-            }
-        } else {
-            Err(UwbPacketError::NoMagicString(String::from_utf8(vec[0 .. 7].to_vec()).unwrap()))
+        let (body, ranging_bytes) = wire::parse_framed(&vec)?;
+
+        match wire::deserialize::<Self>(body) {
+            Ok(mut packet) => {
+                // Put the trailing ranging data into the message, so it can be restored if the message is serialized again.
+                packet.ranging_bytes = ranging_bytes;
+                Ok(packet)
+            },
+            Err(_e) => Err(UwbPacketError::SerializeError),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum GameMode {
     /// The controller is currently not in a game session.
     Idle,
@@ -64,9 +100,112 @@ pub enum GameMode {
     /// your own controller within range of another, try to push it and it may take on the color of yours. The game is
     /// finished and a winner may be declared when all controllers have the same color.
     Territory,
+    /// A user-defined game mode, identified by a name which the firmware maps to a behavior it has loaded at runtime.
+    /// The name must not be empty or longer than [`MAX_CUSTOM_MODE_LEN`] bytes, see [`GameMode::custom`].
+    Custom(String),
+}
+
+/// The maximum length of the name of a `GameMode::Custom`, in bytes, keeping `StartRound` packets small.
+pub const MAX_CUSTOM_MODE_LEN: usize = 32;
+
+impl GameMode {
+    /// A custom game mode with the given name, or `FrameError::UnknownGameMode` if the name is empty or too long.
+    pub fn custom(name: impl Into<String>) -> Result<Self, FrameError> {
+        let name = name.into();
+
+        if name.is_empty() || name.len() > MAX_CUSTOM_MODE_LEN {
+            Err(FrameError::UnknownGameMode)
+        } else {
+            Ok(Self::Custom(name))
+        }
+    }
+
+    /// Check the parameters of the mode, which currently only custom modes have: their name must be valid for
+    /// [`GameMode::custom`], returning `FrameError::UnknownGameMode` otherwise. Built-in modes are always valid.
+    ///
+    /// Modes built from the enum variants directly bypass the checks of `GameMode::custom`, so anything constructing a
+    /// `StartRound` runs this, see [`UwbMessage::start_round`].
+    pub fn validate(&self) -> Result<(), FrameError> {
+        match self {
+            Self::Idle | Self::LastOneStanding | Self::Territory => Ok(()),
+            Self::Custom(name) => Self::custom(name.as_str()).map(|_mode| ()),
+        }
+    }
+
+    /// Whether a game may switch from this mode to `next`. Ending a round, going to `Idle`, is always allowed, and any
+    /// game may be started from `Idle`, but a game can't be started while another one (or the same one) is still active.
+    pub fn can_transition_to(&self, next: &GameMode) -> bool {
+        *self == Self::Idle || *next == Self::Idle
+    }
+
+    /// A stable numeric identifier of the mode, for components which can't handle enum names, like C firmware modules.
+    ///
+    /// | `GameMode`        | ID |
+    /// |-------------------|----|
+    /// | `Idle`            | 0  |
+    /// | `LastOneStanding` | 1  |
+    /// | `Territory`       | 2  |
+    /// | `Custom`          | 3  |
+    ///
+    /// These assignments never change, new modes get the next free number. All custom modes share the same ID, so their
+    /// name has to be transported separately.
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Idle => 0,
+            Self::LastOneStanding => 1,
+            Self::Territory => 2,
+            Self::Custom(_) => 3,
+        }
+    }
+}
+
+/// Looks up a built-in mode by its numeric identifier, see [`GameMode::as_u8`].
+///
+/// Custom modes can't be restored without their name, so their ID returns `FrameError::UnknownGameMode` just like unassigned IDs.
+impl TryFrom<u8> for GameMode {
+    type Error = FrameError;
+
+    fn try_from(id: u8) -> Result<Self, FrameError> {
+        match id {
+            0 => Ok(Self::Idle),
+            1 => Ok(Self::LastOneStanding),
+            2 => Ok(Self::Territory),
+            _ => Err(FrameError::UnknownGameMode),
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// Formats built-in modes in kebab-case, like `last-one-standing`, and custom modes as `custom:<name>`.
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Idle => write!(f, "idle"),
+            Self::LastOneStanding => write!(f, "last-one-standing"),
+            Self::Territory => write!(f, "territory"),
+            Self::Custom(name) => write!(f, "custom:{}", name),
+        }
+    }
+}
+
+/// Parses the names produced by the `Display` implementation.
+impl FromStr for GameMode {
+    type Err = FrameError;
+
+    fn from_str(s: &str) -> Result<Self, FrameError> {
+        match s {
+            "idle" => Ok(Self::Idle),
+            "last-one-standing" => Ok(Self::LastOneStanding),
+            "territory" => Ok(Self::Territory),
+            _ => match s.strip_prefix("custom:") {
+                Some(name) => Self::custom(name),
+                None => Err(FrameError::UnknownGameMode),
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UwbMessage {
     Acknowledged {
         /// The UUID of the message being acknowledged.
@@ -93,12 +232,102 @@ pub enum UwbMessage {
     Ping,
     /// Responsitivity check response to a `Ping` message.
     Pong,
+    /// Ask a controller for its current global brightness, answered with a `BrightnessReport`.
+    BrightnessQuery,
+    /// The current global brightness of the sending controller as a percentage between 0.0 and 1.0.
+    BrightnessReport(f32),
+    /// Sent by a master to move a controller over to another master. The controller says `Bye` to its current master
+    /// and sends a `JoinRequest` to `new_master_id` in `universe`.
+    Redirect {
+        new_master_id: u16,
+        universe: u8,
+    },
+    /// Broadcast by a freshly powered node to enumerate the masters and peers within range before sending a `JoinRequest`.
+    Discover,
+    /// Response to a `Discover` broadcast, describing the replying node so the joining node can pick the best master.
+    DiscoverReply {
+        /// The assigned ID of the replying node.
+        node_id: u16,
+        /// Whether the replying node is a master node accepting `JoinRequest`s.
+        is_master: bool,
+        /// The universe the replying node is currently part of.
+        universe: u8,
+        /// How busy the replying node is, from 0 (idle) to 255 (fully loaded).
+        load: u8,
+    },
+    /// Smoothly ramp the global brightness from its current value to `target` (between 0.0 and 1.0). Firmware should
+    /// interpolate linearly over `duration_ms` instead of jumping to the target, which causes visible flicker.
+    FadeBrightness {
+        target: f32,
+        duration_ms: u32,
+    },
+}
+
+impl UwbMessage {
+    /// A `StartRound` with the given mode, or the error of [`GameMode::validate`] if the mode is invalid.
+    pub fn start_round(mode: GameMode) -> Result<Self, FrameError> {
+        mode.validate()?;
+        Ok(Self::StartRound(mode))
+    }
+}
+
+/// Bridges legacy packet messages to frame payloads.
+///
+/// | `UwbMessage`      | `ControllerMessage`           |
+/// |-------------------|-------------------------------|
+/// | `JoinRequest`     | `JoinRequest`                 |
+/// | `Welcome`         | `JoinResponse`                |
+/// | `Discover`        | `Discover`                    |
+/// | `DiscoverReply`   | `DiscoverReply`               |
+/// | `FadeBrightness`  | `FadeBrightness`              |
+/// | `Redirect`        | `Redirect`                    |
+/// | `BrightnessQuery` | `BrightnessQuery`             |
+/// | `BrightnessReport`| `BrightnessReport`            |
+///
+/// `Acknowledged` and `Tick` map to a `ProtocolMessage` instead, every other message returns `FrameError::NoFrameEquivalent`.
+impl TryFrom<UwbMessage> for ControllerMessage {
+    type Error = FrameError;
+
+    fn try_from(msg: UwbMessage) -> Result<Self, FrameError> {
+        match msg {
+            UwbMessage::JoinRequest => Ok(Self::JoinRequest),
+            UwbMessage::Welcome { controller_id } => Ok(Self::JoinResponse { assigned_id: controller_id }),
+            UwbMessage::Discover => Ok(Self::Discover),
+            UwbMessage::DiscoverReply { node_id, is_master, universe, load } => Ok(Self::DiscoverReply { node_id, is_master, universe, load }),
+            UwbMessage::FadeBrightness { target, duration_ms } => Ok(Self::FadeBrightness { target, duration_ms }),
+            UwbMessage::Redirect { new_master_id, universe } => Ok(Self::Redirect { new_master_id, universe }),
+            UwbMessage::BrightnessQuery => Ok(Self::BrightnessQuery),
+            UwbMessage::BrightnessReport(brightness) => Ok(Self::BrightnessReport { brightness }),
+            _ => Err(FrameError::NoFrameEquivalent),
+        }
+    }
+}
+
+/// Bridges legacy packet messages to frame payloads.
+///
+/// | `UwbMessage`      | `ProtocolMessage`             |
+/// |-------------------|-------------------------------|
+/// | `Acknowledged`    | `Acknowledged`, with the UUID as message ID |
+/// | `Tick`            | `Tick`                        |
+///
+/// Every other message returns `FrameError::NoFrameEquivalent`.
+impl TryFrom<UwbMessage> for ProtocolMessage {
+    type Error = FrameError;
+
+    fn try_from(msg: UwbMessage) -> Result<Self, FrameError> {
+        match msg {
+            UwbMessage::Acknowledged { uuid } => Ok(Self::Acknowledged { message_id: uuid.to_string() }),
+            UwbMessage::Tick(tick) => Ok(Self::Tick(tick)),
+            _ => Err(FrameError::NoFrameEquivalent),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "serde")]
     #[test]
     fn serialize_deserialize_join_request() {
         let packet = UwbPacket {
@@ -110,10 +339,11 @@ mod tests {
             lifetime: 1,
         };
 
-        let encoded = Vec::from(packet.clone());
+        let encoded = packet.to_bytes().unwrap();
         assert_eq!(packet, UwbPacket::try_from(encoded).unwrap());
     }
 
+    #[cfg(feature = "serde")]
     #[test]
     fn serialize_deserialize_welcome() {
         let packet = UwbPacket {
@@ -127,7 +357,318 @@ mod tests {
             lifetime: 1,
         };
 
-        let encoded = Vec::from(packet.clone());
+        let encoded = packet.to_bytes().unwrap();
         assert_eq!(packet, UwbPacket::try_from(encoded).unwrap());
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_deserialize_fade_brightness() {
+        let packet = UwbPacket {
+            sender_id: 0,
+            target_id: None,
+            timestamp: "now".to_string(),
+            ranging_bytes: [0, 0, 0, 0],
+            message:   UwbMessage::FadeBrightness {
+                target: 0.25,
+                duration_ms: 1500,
+            },
+            lifetime: 1,
+        };
+
+        let encoded = packet.to_bytes().unwrap();
+        assert_eq!(packet, UwbPacket::try_from(encoded).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_deserialize_discover() {
+        let packet = UwbPacket {
+            sender_id: u16::MAX,
+            target_id: None,
+            timestamp: "now".to_string(),
+            ranging_bytes: [0, 0, 0, 0],
+            message:   UwbMessage::Discover,
+            lifetime: 1,
+        };
+
+        let encoded = packet.to_bytes().unwrap();
+        assert_eq!(packet, UwbPacket::try_from(encoded).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_deserialize_discover_reply() {
+        let packet = UwbPacket {
+            sender_id: 0,
+            target_id: Some(u16::MAX),
+            timestamp: "now".to_string(),
+            ranging_bytes: [1, 2, 3, 4],
+            message:   UwbMessage::DiscoverReply {
+                node_id: 0,
+                is_master: true,
+                universe: 3,
+                load: 42,
+            },
+            lifetime: 1,
+        };
+
+        let encoded = packet.to_bytes().unwrap();
+        assert_eq!(packet, UwbPacket::try_from(encoded).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_deserialize_redirect() {
+        let packet = UwbPacket {
+            sender_id: 0,
+            target_id: Some(5),
+            timestamp: "now".to_string(),
+            ranging_bytes: [0, 0, 0, 0],
+            message:   UwbMessage::Redirect {
+                new_master_id: 2,
+                universe: 1,
+            },
+            lifetime: 1,
+        };
+
+        let encoded = packet.to_bytes().unwrap();
+        assert_eq!(packet, UwbPacket::try_from(encoded).unwrap());
+        assert_eq!(
+            ControllerMessage::try_from(packet.message),
+            Ok(ControllerMessage::Redirect { new_master_id: 2, universe: 1 }),
+        );
+    }
+
+    #[test]
+    fn join_request_to_controller_message() {
+        assert_eq!(ControllerMessage::try_from(UwbMessage::JoinRequest), Ok(ControllerMessage::JoinRequest));
+    }
+
+    #[test]
+    fn welcome_to_controller_message() {
+        assert_eq!(
+            ControllerMessage::try_from(UwbMessage::Welcome { controller_id: 4 }),
+            Ok(ControllerMessage::JoinResponse { assigned_id: 4 }),
+        );
+    }
+
+    #[test]
+    fn discover_to_controller_message() {
+        assert_eq!(ControllerMessage::try_from(UwbMessage::Discover), Ok(ControllerMessage::Discover));
+    }
+
+    #[test]
+    fn discover_reply_to_controller_message() {
+        assert_eq!(
+            ControllerMessage::try_from(UwbMessage::DiscoverReply { node_id: 0, is_master: true, universe: 1, load: 2 }),
+            Ok(ControllerMessage::DiscoverReply { node_id: 0, is_master: true, universe: 1, load: 2 }),
+        );
+    }
+
+    #[test]
+    fn fade_brightness_to_controller_message() {
+        assert_eq!(
+            ControllerMessage::try_from(UwbMessage::FadeBrightness { target: 0.5, duration_ms: 100 }),
+            Ok(ControllerMessage::FadeBrightness { target: 0.5, duration_ms: 100 }),
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn brightness_read_back_round_trip() {
+        let packet = UwbPacket {
+            sender_id: 3,
+            target_id: Some(0),
+            timestamp: "now".to_string(),
+            ranging_bytes: [0, 0, 0, 0],
+            message:   UwbMessage::BrightnessReport(0.25),
+            lifetime: 1,
+        };
+
+        assert_eq!(packet, UwbPacket::try_from(packet.to_bytes().unwrap()).unwrap());
+        assert_eq!(ControllerMessage::try_from(UwbMessage::BrightnessQuery), Ok(ControllerMessage::BrightnessQuery));
+        assert_eq!(
+            ControllerMessage::try_from(UwbMessage::BrightnessReport(0.25)),
+            Ok(ControllerMessage::BrightnessReport { brightness: 0.25 }),
+        );
+    }
+
+    #[test]
+    fn acknowledged_to_protocol_message() {
+        let uuid = Uuid::new_v4();
+
+        assert_eq!(
+            ProtocolMessage::try_from(UwbMessage::Acknowledged { uuid }),
+            Ok(ProtocolMessage::Acknowledged { message_id: uuid.to_string() }),
+        );
+    }
+
+    #[test]
+    fn tick_to_protocol_message() {
+        assert_eq!(ProtocolMessage::try_from(UwbMessage::Tick(9)), Ok(ProtocolMessage::Tick(9)));
+    }
+
+    #[test]
+    fn unmapped_messages_have_no_frame_equivalent() {
+        assert_eq!(ControllerMessage::try_from(UwbMessage::Tick(9)), Err(FrameError::NoFrameEquivalent));
+        assert_eq!(ProtocolMessage::try_from(UwbMessage::JoinRequest), Err(FrameError::NoFrameEquivalent));
+        assert_eq!(ControllerMessage::try_from(UwbMessage::Ping), Err(FrameError::NoFrameEquivalent));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_deserialize_custom_game_mode() {
+        let packet = UwbPacket {
+            sender_id: 0,
+            target_id: None,
+            timestamp: "now".to_string(),
+            ranging_bytes: [0, 0, 0, 0],
+            message:   UwbMessage::StartRound(GameMode::custom("hot-potato").unwrap()),
+            lifetime: 1,
+        };
+
+        let encoded = packet.to_bytes().unwrap();
+        assert_eq!(packet, UwbPacket::try_from(encoded).unwrap());
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&packet.message).unwrap();
+            assert_eq!(packet.message, serde_json::from_str::<UwbMessage>(&json).unwrap());
+        }
+    }
+
+    #[test]
+    fn game_mode_names_round_trip() {
+        for mode in [GameMode::Idle, GameMode::LastOneStanding, GameMode::Territory, GameMode::custom("foo").unwrap()] {
+            assert_eq!(mode, mode.to_string().parse().unwrap());
+        }
+
+        assert_eq!(GameMode::custom("foo").unwrap().to_string(), "custom:foo");
+        assert_eq!("custom:".parse::<GameMode>(), Err(FrameError::UnknownGameMode));
+        assert_eq!("chess".parse::<GameMode>(), Err(FrameError::UnknownGameMode));
+        assert_eq!(GameMode::custom("x".repeat(MAX_CUSTOM_MODE_LEN + 1)), Err(FrameError::UnknownGameMode));
+    }
+
+    #[test]
+    fn start_round_validates_mode() {
+        assert_eq!(UwbMessage::start_round(GameMode::Territory), Ok(UwbMessage::StartRound(GameMode::Territory)));
+        assert_eq!(GameMode::custom("hot-potato").unwrap().validate(), Ok(()));
+
+        for invalid in [GameMode::Custom(String::new()), GameMode::Custom("x".repeat(MAX_CUSTOM_MODE_LEN + 1))] {
+            assert_eq!(invalid.validate(), Err(FrameError::UnknownGameMode));
+            assert_eq!(UwbMessage::start_round(invalid), Err(FrameError::UnknownGameMode));
+        }
+    }
+
+    #[test]
+    fn game_mode_transitions() {
+        let custom = GameMode::custom("hot-potato").unwrap();
+        for mode in [GameMode::Idle, GameMode::LastOneStanding, GameMode::Territory, custom.clone()] {
+            assert!(GameMode::Idle.can_transition_to(&mode));
+            assert!(mode.can_transition_to(&GameMode::Idle));
+        }
+
+        assert!(!GameMode::Territory.can_transition_to(&GameMode::LastOneStanding));
+        assert!(!GameMode::Territory.can_transition_to(&GameMode::Territory));
+        assert!(!custom.can_transition_to(&GameMode::custom("musical-chairs").unwrap()));
+    }
+
+    #[test]
+    fn game_mode_ids_round_trip() {
+        for mode in [GameMode::Idle, GameMode::LastOneStanding, GameMode::Territory] {
+            assert_eq!(GameMode::try_from(mode.as_u8()), Ok(mode));
+        }
+
+        assert_eq!(GameMode::Territory.as_u8(), 2);
+        assert_eq!(GameMode::custom("foo").unwrap().as_u8(), 3);
+        assert_eq!(GameMode::try_from(3), Err(FrameError::UnknownGameMode));
+        assert_eq!(GameMode::try_from(200), Err(FrameError::UnknownGameMode));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn oversized_packet_is_an_error() {
+        let packet = UwbPacket {
+            sender_id: 0,
+            target_id: None,
+            timestamp: "x".repeat(crate::frame::MAX_FRAME_SIZE),
+            ranging_bytes: [0, 0, 0, 0],
+            message:   UwbMessage::Bye,
+            lifetime: 1,
+        };
+
+        assert_eq!(packet.to_bytes(), Err(UwbPacketError::SerializeError));
+        assert_eq!(Vec::try_from(packet), Err(UwbPacketError::SerializeError));
+    }
+
+    /// The bytes of a packet encoded by the first release, which new variants must not change.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn set_brightness_keeps_its_legacy_bytes() {
+        let packet = UwbPacket {
+            sender_id: 7,
+            target_id: Some(3),
+            timestamp: "now".to_string(),
+            ranging_bytes: [1, 2, 3, 4],
+            message:   UwbMessage::SetBrightness(0.5),
+            lifetime: 2,
+        };
+        let golden = [
+            b"LEDswarm".as_slice(),
+            &[7, 0],
+            &[1, 3, 0],
+            &[3, 0, 0, 0, 0, 0, 0, 0], b"now",
+            &[1, 2, 3, 4],
+            // The discriminant of `SetBrightness`, followed by 0.5.
+            &[4, 0, 0, 0], &[0, 0, 0, 0x3F],
+            &[2],
+            &[1, 2, 3, 4],
+        ]
+        .concat();
+
+        assert_eq!(packet.to_bytes().unwrap(), golden);
+        assert_eq!(UwbPacket::try_from(golden), Ok(packet));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn legacy_variants_keep_their_discriminants() {
+        use crate::frame::ClientMessage;
+
+        let legacy = [
+            (UwbMessage::StartRound(GameMode::Idle), 5),
+            (UwbMessage::EndRound, 6),
+            (UwbMessage::Tick(0), 7),
+            (UwbMessage::Ping, 8),
+            (UwbMessage::Pong, 9),
+        ];
+        for (message, discriminant) in legacy {
+            let bytes = wire::bincode_options().serialize(&message).unwrap();
+            assert_eq!(bytes[.. 4], [discriminant, 0, 0, 0], "{:?}", message);
+        }
+
+        let client = [(ClientMessage::SetBrightness(0.0), 0), (ClientMessage::StartRound(String::new()), 1), (ClientMessage::EndRound, 2)];
+        for (message, discriminant) in client {
+            let bytes = wire::bincode_options().serialize(&message).unwrap();
+            assert_eq!(bytes[.. 4], [discriminant, 0, 0, 0], "{:?}", message);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn verify_magic_only() {
+        let packet = UwbPacket {
+            sender_id: 0,
+            target_id: None,
+            timestamp: "now".to_string(),
+            ranging_bytes: [0, 0, 0, 0],
+            message:   UwbMessage::JoinRequest,
+            lifetime: 1,
+        };
+
+        assert!(UwbPacket::verify_magic_only(&packet.to_bytes().unwrap()));
+        assert!(!UwbPacket::verify_magic_only(b"foreign packet"));
+        assert!(!UwbPacket::verify_magic_only(b"LED"));
+    }
+}
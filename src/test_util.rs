@@ -0,0 +1,158 @@
+//! Helpers for downstream crates to test their own frame construction against the wire format, available with the `test-util` feature.
+//!
+//! ```rust
+//! use ledswarm_protocol::{test_util::{arbitrary_frame, assert_roundtrip}, frame::Frame};
+//!
+//! assert_roundtrip(&Frame::join_request(42));
+//!
+//! for seed in 0 .. 100 {
+//!     assert_roundtrip(&arbitrary_frame(seed));
+//! }
+//! ```
+
+use crate::frame::{AbortReason, Frame, FramePayload, ClientMessage, ControllerMessage, ProtocolMessage};
+use crate::frame::ranging::DW1000_TIMESTAMP_MASK;
+
+/// Encode and decode a frame, panicking with a field-by-field diff if the decoded frame is not semantically equal to the original.
+#[cfg(feature = "serde")]
+pub fn assert_roundtrip(frame: &Frame) {
+    let bytes = match frame.to_bytes() {
+        Ok(bytes) => bytes,
+        Err(e) => panic!("frame could not be encoded: {:?}\n{:#?}", e, frame),
+    };
+
+    let decoded = match Frame::try_from(bytes.as_slice()) {
+        Ok(decoded) => decoded,
+        Err(e) => panic!("encoded frame could not be decoded: {:?}\n{:#?}", e, frame),
+    };
+
+    if !frame.semantically_eq(&decoded) {
+        let diffs: Vec<String> = frame
+            .diff(&decoded)
+            .into_iter()
+            .map(|diff| format!("  {}: {} != {}", diff.field, diff.left, diff.right))
+            .collect();
+        panic!("frame changed in a round-trip through the wire format:\n{}", diffs.join("\n"));
+    }
+}
+
+/// A deterministic pseudo-random frame, producing the same frame for the same seed.
+///
+/// Payload and header fields are varied, but the frame always stays valid, so it is useful for property-style tests.
+pub fn arbitrary_frame(seed: u64) -> Frame {
+    let mut rng = SplitMix64(seed);
+
+    let payload = match rng.below(30) {
+        0 => FramePayload::ControllerMessage(ControllerMessage::JoinRequest),
+        1 => FramePayload::ControllerMessage(ControllerMessage::JoinResponse { assigned_id: 1 + rng.below(u16::MAX as u64 - 1) as u16 }),
+        2 => FramePayload::ControllerMessage(ControllerMessage::Discover),
+        3 => FramePayload::ControllerMessage(ControllerMessage::DiscoverReply {
+            node_id:   rng.next() as u16,
+            is_master: rng.below(2) == 0,
+            universe:  rng.next() as u8,
+            load:      rng.next() as u8,
+        }),
+        4 => FramePayload::ControllerMessage(ControllerMessage::FadeBrightness {
+            target:      rng.unit(),
+            duration_ms: rng.next() as u32,
+        }),
+        5 => FramePayload::ProtocolMessage(ProtocolMessage::Acknowledged { message_id: rng.message_id() }),
+        6 => FramePayload::ProtocolMessage(ProtocolMessage::Tick(rng.next() as u16)),
+        7 => FramePayload::ProtocolMessage(ProtocolMessage::KeepAlive { interval_ticks: rng.next() as u16 }),
+        8 => FramePayload::ClientMessage(ClientMessage::SetBrightness(rng.unit())),
+        9 => FramePayload::ClientMessage(ClientMessage::StartRound(rng.message_id())),
+        10 => FramePayload::ClientMessage(ClientMessage::EndRound),
+        11 => FramePayload::ControllerMessage(ControllerMessage::TeamAssign {
+            controller_id: rng.next() as u16,
+            team:          rng.below(4) as u8,
+            color:         (rng.next() as u8, rng.next() as u8, rng.next() as u8),
+        }),
+        12 => FramePayload::ControllerMessage(ControllerMessage::TeamScore { team: rng.below(4) as u8, score: rng.next() as i32 }),
+        13 => FramePayload::ProtocolMessage(ProtocolMessage::Election { candidate_id: rng.next() as u16 }),
+        14 => FramePayload::ProtocolMessage(ProtocolMessage::Coordinator { master_id: rng.next() as u16 }),
+        15 => FramePayload::ControllerMessage([ControllerMessage::ReadyCheck, ControllerMessage::Ready, ControllerMessage::NotReady][rng.below(3) as usize].clone()),
+        16 => FramePayload::ProtocolMessage(ProtocolMessage::clock_drift(rng.next() as u16, rng.next() as u16)),
+        17 => FramePayload::Batch((0 .. rng.below(4)).map(|_| FramePayload::ProtocolMessage(ProtocolMessage::Tick(rng.next() as u16))).collect()),
+        18 => FramePayload::ControllerMessage(ControllerMessage::Emote { emote: rng.next() as u8 }),
+        19 => FramePayload::ControllerMessage(ControllerMessage::SetUniverse { universe: rng.next() as u8 }),
+        20 => FramePayload::ControllerMessage(ControllerMessage::BrightnessReport { brightness: rng.unit() }),
+        21 => FramePayload::ControllerMessage(ControllerMessage::RangingResponse { t_reply: rng.next() & DW1000_TIMESTAMP_MASK }),
+        22 => FramePayload::ProtocolMessage(ProtocolMessage::Hello { version: rng.next() as u8, capabilities: rng.next() as u32 }),
+        23 => FramePayload::ControllerMessage(ControllerMessage::Vote { topic: rng.next() as u8, choice: rng.next() as u8 }),
+        24 => FramePayload::ProtocolMessage(ProtocolMessage::Compressed { algo: rng.next() as u8, data: (0 .. rng.below(16)).map(|_| rng.next() as u8).collect() }),
+        25 => FramePayload::ProtocolMessage(ProtocolMessage::AcknowledgedBatch { message_ids: (0 .. rng.below(4)).map(|_| rng.message_id()).collect() }),
+        26 => FramePayload::ClientMessage(if rng.below(2) == 0 { ClientMessage::Ping { nonce: rng.next() as u32 } } else { ClientMessage::Pong { nonce: rng.next() as u32 } }),
+        27 => FramePayload::ProtocolMessage(ProtocolMessage::Encrypted { key_id: rng.next() as u8, data: (0 .. rng.below(16)).map(|_| rng.next() as u8).collect() }),
+        28 => FramePayload::ProtocolMessage(ProtocolMessage::Abort {
+            message_id: rng.message_id(),
+            reason:     [AbortReason::Timeout, AbortReason::Cancelled, AbortReason::Resource][rng.below(3) as usize],
+        }),
+        _ => FramePayload::Empty,
+    };
+
+    let mut frame = Frame::new();
+    frame.payload = payload;
+    frame.header.timestamp = chrono::DateTime::from_timestamp(1_700_000_000 + rng.below(100_000_000) as i64, 0)
+        .unwrap()
+        .to_rfc3339();
+    frame.header.message_id = rng.message_id();
+    frame.header.lifetime = rng.below(8) as u8 + 1;
+    frame.header.sender_id = rng.next() as u16;
+    frame.header.target_id = if rng.below(2) == 0 { None } else { Some(rng.next() as u16) };
+    frame.header.requires_acknowledgement = rng.below(2) == 0;
+    frame.header.acked_message_id = if rng.below(4) == 0 { Some(rng.message_id()) } else { None };
+    frame.header.current_tick = rng.next() as u16;
+    frame.header.universe = rng.next() as u8;
+    frame.header.ranging_bytes = (rng.next() as u32).to_le_bytes();
+    frame.header.ack_timeout_ticks = if rng.below(4) == 0 { Some(rng.next() as u16) } else { None };
+    frame.header.deadline_tick = if rng.below(4) == 0 { Some(rng.next() as u16) } else { None };
+
+    frame
+}
+
+/// A tiny, dependency-free pseudo-random number generator, see <https://prng.di.unimi.it/splitmix64.c>.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// A value between 0.0 and 1.0 in steps of 0.001, never NaN.
+    fn unit(&mut self) -> f32 {
+        self.below(1001) as f32 / 1000.0
+    }
+
+    /// A ten character ID using the same alphabet as `nanoid`.
+    fn message_id(&mut self) -> String {
+        const ALPHABET: &[u8] = b"_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        (0 .. 10).map(|_| ALPHABET[self.below(ALPHABET.len() as u64) as usize] as char).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn arbitrary_frames_round_trip() {
+        for seed in 0 .. 500 {
+            assert_roundtrip(&arbitrary_frame(seed));
+        }
+    }
+
+    #[test]
+    fn arbitrary_frame_is_deterministic() {
+        assert_eq!(arbitrary_frame(7), arbitrary_frame(7));
+        assert_ne!(arbitrary_frame(7), arbitrary_frame(8));
+    }
+}
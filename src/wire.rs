@@ -0,0 +1,155 @@
+//! The byte-level framing shared by `UwbPacket` and `Frame`: the magic string, an opaque body and four trailing ranging bytes.
+//!
+//! ```text
+//! +------------+------------------+---------------+
+//! | "LEDswarm" | body             | ranging bytes |
+//! | 8 bytes    | variable length  | 4 bytes       |
+//! +------------+------------------+---------------+
+//! ```
+//!
+//! `UwbPacket` uses its bincode serialization as the body, while `Frame` prepends a format tag and a length prefix to its
+//! serialization, see [`crate::frame`].
+
+#[cfg(feature = "serde")]
+use bincode::Options;
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde_derive::{Serialize, Deserialize};
+
+#[cfg(feature = "serde")]
+use crate::frame::MAX_FRAME_SIZE;
+
+/// The magic string every encoded packet and frame starts with.
+pub const MAGIC: &[u8; 8] = b"LEDswarm";
+
+/// The number of ranging bytes following the body.
+pub const RANGING_LEN: usize = 4;
+
+/// Errors in the magic string and ranging framing, before the body is decoded.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WireError {
+    /// The buffer ended early, `expected` bytes were needed but only `actual` bytes were available.
+    TooShort { expected: usize, actual: usize },
+    /// The buffer does not start with the magic string, so it is not a LEDswarm datagram. Contains the bytes found instead.
+    NoMagicString(String),
+}
+
+/// Wrap a body with the magic string and the ranging bytes.
+pub fn frame_bytes(magic: &[u8; 8], body: &[u8], ranging: [u8; 4]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(magic.len() + body.len() + RANGING_LEN);
+
+    buffer.extend_from_slice(magic);
+    buffer.extend_from_slice(body);
+    buffer.extend_from_slice(&ranging);
+
+    buffer
+}
+
+/// Split a complete datagram into its body and ranging bytes, checking the magic string.
+///
+/// The ranging bytes are taken from the very end of `bytes`, so callers which know the length of the datagram should pass
+/// exactly that many bytes.
+pub fn parse_framed(bytes: &[u8]) -> Result<(&[u8], [u8; 4]), WireError> {
+    check_magic(bytes)?;
+
+    if bytes.len() < MAGIC.len() + RANGING_LEN {
+        return Err(WireError::TooShort { expected: MAGIC.len() + RANGING_LEN, actual: bytes.len() });
+    }
+
+    let (body, ranging) = bytes[MAGIC.len() ..].split_at(bytes.len() - MAGIC.len() - RANGING_LEN);
+    Ok((body, [ranging[0], ranging[1], ranging[2], ranging[3]]))
+}
+
+/// Whether `bytes` start with the magic string, as a cheap filter for foreign traffic. Short buffers don't.
+pub fn has_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Check that `bytes` start with the magic string.
+pub fn check_magic(bytes: &[u8]) -> Result<(), WireError> {
+    if bytes.len() < MAGIC.len() {
+        Err(WireError::TooShort { expected: MAGIC.len(), actual: bytes.len() })
+    } else if !bytes.starts_with(MAGIC) {
+        Err(WireError::NoMagicString(String::from_utf8_lossy(&bytes[.. MAGIC.len()]).into_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// The bincode configuration of every packet and frame body, the single place defining how they are encoded.
+///
+/// Integers are encoded with a fixed width in little-endian byte order, which is the configuration of `bincode::serialize`,
+/// so bodies stay compatible with data encoded before this was configurable. Encoding and decoding is limited to
+/// [`MAX_FRAME_SIZE`] bytes, and decoding ignores trailing bytes, since the framing around the body determines its end.
+#[cfg(feature = "serde")]
+pub fn bincode_options() -> impl Options + Copy {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_little_endian()
+        .allow_trailing_bytes()
+        .with_limit(MAX_FRAME_SIZE as u64)
+}
+
+/// Decode untrusted bytes received over the air with [`bincode_options`].
+///
+/// The bytes are read through `deserialize_from`, because bincode ignores the size limit when deserializing from a slice directly.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> bincode::Result<T> {
+    bincode_options().deserialize_from(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use crate::{Frame, UwbPacket, UwbMessage};
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn packet_and_frame_share_framing() {
+        let packet = UwbPacket {
+            sender_id: 0,
+            target_id: None,
+            timestamp: "now".to_string(),
+            ranging_bytes: [1, 2, 3, 4],
+            message:   UwbMessage::Ping,
+            lifetime: 1,
+        };
+        let mut frame = Frame::tick(3);
+        frame.header.ranging_bytes = [5, 6, 7, 8];
+
+        let packet_bytes = packet.to_bytes().unwrap();
+        let (body, ranging) = parse_framed(&packet_bytes).unwrap();
+        assert_eq!(ranging, [1, 2, 3, 4]);
+        assert_eq!(body, bincode::serialize(&packet).unwrap().as_slice());
+        assert_eq!(packet_bytes, frame_bytes(MAGIC, body, ranging));
+
+        let frame_bytes_ = frame.to_bytes().unwrap();
+        let (body, ranging) = parse_framed(&frame_bytes_).unwrap();
+        assert_eq!(ranging, [5, 6, 7, 8]);
+        let header = bincode::serialize(&frame.header).unwrap();
+        assert_eq!(&body[3 .. 3 + header.len()], header.as_slice());
+        assert_eq!(&body[3 + header.len() + 2 ..], bincode::serialize(&frame.payload).unwrap().as_slice());
+        assert_eq!(frame_bytes_, frame_bytes(MAGIC, body, ranging));
+    }
+
+    #[test]
+    fn parse_framed_errors() {
+        assert_eq!(parse_framed(b"LEDs"), Err(WireError::TooShort { expected: 8, actual: 4 }));
+        assert_eq!(parse_framed(b"LEDswarm12"), Err(WireError::TooShort { expected: 12, actual: 10 }));
+        assert_eq!(parse_framed(b"LEDSWARM1234"), Err(WireError::NoMagicString("LEDSWARM".to_string())));
+        assert_eq!(parse_framed(b"LEDswarm1234"), Ok((&b""[..], *b"1234")));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bincode_options_produce_stable_bytes() {
+        let bytes = bincode_options().serialize(&(0x1234u16, "ab", Some(1u8))).unwrap();
+        assert_eq!(bytes, [0x34, 0x12, 2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', 1, 1]);
+
+        let bytes = bincode_options().serialize(&crate::frame::ProtocolMessage::Tick(0x0102)).unwrap();
+        assert_eq!(bytes, [1, 0, 0, 0, 2, 1]);
+    }
+}